@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: GPL-2.0
+//
+// minimal XDP program used by `xdp-check probe` to exercise the verifier
+// and the generic/native attach paths
+//
+// rebuild with: clang -O2 -target bpf -c xdp_pass.c -o xdp_pass.o
+
+#define SEC(name) __attribute__((section(name), used))
+
+#define XDP_PASS 2
+
+struct xdp_md;
+
+SEC("xdp")
+int xdp_pass(struct xdp_md *ctx)
+{
+	return XDP_PASS;
+}
+
+char _license[] SEC("license") = "GPL";
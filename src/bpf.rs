@@ -0,0 +1,658 @@
+use anyhow::{anyhow, bail, Result};
+use nix::errno::Errno;
+use std::ffi::CString;
+use std::fs;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+use std::path::Path;
+
+use crate::output::{CheckResult, CheckStatus};
+
+const BPF_MAP_CREATE: i32 = 0;
+const BPF_MAP_LOOKUP_ELEM: i32 = 1;
+const BPF_PROG_LOAD: i32 = 5;
+const BPF_OBJ_GET: i32 = 7;
+const BPF_MAP_GET_FD_BY_ID: i32 = 14;
+const BPF_OBJ_GET_INFO_BY_FD: i32 = 15;
+const BPF_BTF_GET_FD_BY_ID: i32 = 19;
+const BPF_LINK_GET_FD_BY_ID: i32 = 30;
+const BPF_LINK_GET_NEXT_ID: i32 = 31;
+
+const BPF_PROG_TYPE_XDP: u32 = 6;
+
+const BPF_MAP_TYPE_DEVMAP: u32 = 14;
+const BPF_MAP_TYPE_CPUMAP: u32 = 16;
+const BPF_MAP_TYPE_XSKMAP: u32 = 17;
+const BPF_MAP_TYPE_DEVMAP_HASH: u32 = 25;
+const BPF_MAP_TYPE_RINGBUF: u32 = 27;
+
+const BPF_FUNC_REDIRECT_MAP: i32 = 51;
+const BPF_FUNC_XDP_ADJUST_TAIL: i32 = 65;
+
+/// prog_flags bit for multi-buffer XDP programs (5.18+)
+const BPF_F_XDP_HAS_FRAGS: u32 = 1 << 5;
+
+/// kernel-internal "not supported", leaks to userspace from some bpf paths
+const ENOTSUPP: i32 = 524;
+
+/// leading part of union bpf_attr used by BPF_MAP_CREATE
+#[repr(C)]
+#[derive(Default)]
+struct MapCreateAttr {
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+    inner_map_fd: u32,
+    numa_node: u32,
+    map_name: [u8; 16],
+    map_ifindex: u32,
+    btf_fd: u32,
+    btf_key_type_id: u32,
+    btf_value_type_id: u32,
+    btf_vmlinux_value_type_id: u32,
+}
+
+/// leading part of union bpf_attr used by BPF_PROG_LOAD
+#[repr(C)]
+#[derive(Default)]
+struct ProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+    prog_name: [u8; 16],
+    prog_ifindex: u32,
+    expected_attach_type: u32,
+}
+
+/// union bpf_attr for BPF_OBJ_GET
+#[repr(C)]
+#[derive(Default)]
+struct ObjGetAttr {
+    pathname: u64,
+    bpf_fd: u32,
+    file_flags: u32,
+}
+
+/// union bpf_attr for BPF_OBJ_GET_INFO_BY_FD
+#[repr(C)]
+struct InfoByFdAttr {
+    bpf_fd: u32,
+    info_len: u32,
+    info: u64,
+}
+
+/// union bpf_attr for the *_GET_FD_BY_ID commands
+#[repr(C)]
+#[derive(Default)]
+struct GetFdByIdAttr {
+    id: u32,
+    next_id: u32,
+    open_flags: u32,
+}
+
+/// union bpf_attr for BPF_MAP_LOOKUP_ELEM
+#[repr(C)]
+#[derive(Default)]
+struct MapElemAttr {
+    map_fd: u32,
+    _pad: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+/// struct bpf_link_info, the per-type union kept as raw words
+#[repr(C)]
+#[derive(Default)]
+struct RawLinkInfo {
+    link_type: u32,
+    id: u32,
+    prog_id: u32,
+    _pad: u32,
+    data: [u32; 8],
+}
+
+/// struct bpf_prog_info up to attach_btf_id (6.0)
+#[repr(C)]
+#[derive(Default)]
+struct ProgInfo {
+    prog_type: u32,
+    id: u32,
+    tag: [u8; 8],
+    jited_prog_len: u32,
+    xlated_prog_len: u32,
+    jited_prog_insns: u64,
+    xlated_prog_insns: u64,
+    load_time: u64,
+    created_by_uid: u32,
+    nr_map_ids: u32,
+    map_ids: u64,
+    name: [u8; 16],
+    ifindex: u32,
+    gpl_compatible: u32,
+    netns_dev: u64,
+    netns_ino: u64,
+    nr_jited_ksyms: u32,
+    nr_jited_func_lens: u32,
+    jited_ksyms: u64,
+    jited_func_lens: u64,
+    btf_id: u32,
+    func_info_rec_size: u32,
+    func_info: u64,
+    nr_func_info: u32,
+    nr_line_info: u32,
+    line_info: u64,
+    jited_line_info: u64,
+    nr_jited_line_info: u32,
+    line_info_rec_size: u32,
+    jited_line_info_rec_size: u32,
+    nr_prog_tags: u32,
+    prog_tags: u64,
+    run_time_ns: u64,
+    run_cnt: u64,
+    recursion_misses: u64,
+    verified_insns: u32,
+    attach_btf_obj_id: u32,
+    attach_btf_id: u32,
+    /// explicit so it is zeroed, the kernel rejects non-zero tails with E2BIG
+    _pad: u32,
+}
+
+/// struct bpf_btf_info
+#[repr(C)]
+#[derive(Default)]
+struct BtfInfo {
+    btf: u64,
+    btf_size: u32,
+    id: u32,
+    name: u64,
+    name_len: u32,
+    kernel_btf: u32,
+}
+
+/// struct bpf_insn, registers packed as dst:4 src:4
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Insn {
+    code: u8,
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+const fn insn(code: u8, imm: i32) -> Insn {
+    Insn { code, regs: 0, off: 0, imm }
+}
+
+/// r0 = XDP_PASS
+const MOV_R0_XDP_PASS: Insn = insn(0xb7, 2);
+const EXIT: Insn = insn(0x95, 0);
+const fn call(helper: i32) -> Insn {
+    insn(0x85, helper)
+}
+
+static LICENSE: &[u8] = b"GPL\0";
+
+/// raw bpf(2), returns the new fd for commands that create objects
+fn sys_bpf<T>(cmd: i32, attr: &mut T) -> Result<i32, Errno> {
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd as libc::c_long,
+            attr as *mut T,
+            mem::size_of::<T>() as libc::c_long,
+        )
+    };
+    if res < 0 {
+        Err(Errno::last())
+    } else {
+        Ok(res as i32)
+    }
+}
+
+fn errno_text(errno: Errno) -> String {
+    if errno as i32 == ENOTSUPP {
+        return format!("ENOTSUPP (errno {})", ENOTSUPP);
+    }
+    format!("{} (errno {})", errno, errno as i32)
+}
+
+/// BPF_OBJ_GET_INFO_BY_FD into `info`, pointer fields in `info` select what extra data is copied
+fn obj_info<T>(fd: BorrowedFd, info: &mut T) -> Result<(), Errno> {
+    let mut attr = InfoByFdAttr {
+        bpf_fd: fd.as_raw_fd() as u32,
+        info_len: mem::size_of::<T>() as u32,
+        info: info as *mut T as u64,
+    };
+    sys_bpf(BPF_OBJ_GET_INFO_BY_FD, &mut attr).map(drop)
+}
+
+/// one entry of a program's BTF func_info: where a subprogram starts and its name
+pub struct FuncInfo {
+    pub insn_off: u32,
+    pub name: String,
+}
+
+/// BTF func_info of a loaded program, empty when it was loaded without BTF
+pub fn prog_func_info(prog: BorrowedFd) -> Result<Vec<FuncInfo>> {
+    let mut info = ProgInfo::default();
+    obj_info(prog, &mut info).map_err(|e| anyhow!("BPF_OBJ_GET_INFO_BY_FD failed: {}", errno_text(e)))?;
+
+    if info.nr_func_info == 0 || info.btf_id == 0 {
+        return Ok(Vec::new());
+    }
+
+    // struct bpf_func_info { insn_off, type_id }
+    let count = info.nr_func_info;
+    let rec_size = info.func_info_rec_size.max(8);
+    let mut records = vec![0u8; (count * rec_size) as usize];
+    let mut request = ProgInfo {
+        nr_func_info: count,
+        func_info_rec_size: rec_size,
+        func_info: records.as_mut_ptr() as u64,
+        ..Default::default()
+    };
+    obj_info(prog, &mut request).map_err(|e| anyhow!("Reading func_info failed: {}", errno_text(e)))?;
+
+    let btf = Btf::from_id(info.btf_id)?;
+
+    Ok(records
+        .chunks_exact(rec_size as usize)
+        .map(|record| {
+            let insn_off = u32::from_ne_bytes(record[0..4].try_into().unwrap());
+            let type_id = u32::from_ne_bytes(record[4..8].try_into().unwrap());
+            FuncInfo {
+                insn_off,
+                name: btf.type_name(type_id).unwrap_or("?").to_string(),
+            }
+        })
+        .collect())
+}
+
+/// minimal reader for a standalone (non-split) BTF blob, only type names
+struct Btf {
+    /// name_off per type, index 0 is type id 1
+    name_offsets: Vec<u32>,
+    strings: Vec<u8>,
+}
+
+impl Btf {
+    const MAGIC: u16 = 0xeb9f;
+
+    fn from_id(id: u32) -> Result<Self> {
+        let mut attr = GetFdByIdAttr { id, ..Default::default() };
+        let fd = sys_bpf(BPF_BTF_GET_FD_BY_ID, &mut attr)
+            .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+            .map_err(|e| anyhow!("BPF_BTF_GET_FD_BY_ID {} failed: {}", id, errno_text(e)))?;
+
+        let mut info = BtfInfo::default();
+        obj_info(fd.as_fd(), &mut info).map_err(|e| anyhow!("Reading BTF {} failed: {}", id, errno_text(e)))?;
+
+        let mut data = vec![0u8; info.btf_size as usize];
+        let mut request = BtfInfo {
+            btf: data.as_mut_ptr() as u64,
+            btf_size: info.btf_size,
+            ..Default::default()
+        };
+        obj_info(fd.as_fd(), &mut request).map_err(|e| anyhow!("Reading BTF {} failed: {}", id, errno_text(e)))?;
+
+        Self::parse(&data)
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        let u32_at = |off: usize| -> Result<u32> {
+            data.get(off..off + 4)
+                .map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| anyhow!("Truncated BTF"))
+        };
+
+        if data.len() < 24 || u16::from_ne_bytes([data[0], data[1]]) != Self::MAGIC {
+            bail!("Not a BTF blob");
+        }
+
+        // struct btf_header
+        let hdr_len = u32_at(4)? as usize;
+        let type_start = hdr_len + u32_at(8)? as usize;
+        let type_end = type_start + u32_at(12)? as usize;
+        let str_start = hdr_len + u32_at(16)? as usize;
+        let str_end = str_start + u32_at(20)? as usize;
+
+        let strings = data
+            .get(str_start..str_end)
+            .ok_or_else(|| anyhow!("Truncated BTF string section"))?
+            .to_vec();
+
+        // struct btf_type is 12 bytes, followed by kind specific data
+        let mut name_offsets = Vec::new();
+        let mut off = type_start;
+        while off < type_end {
+            let name_off = u32_at(off)?;
+            let info = u32_at(off + 4)?;
+            let kind = (info >> 24) & 0x1f;
+            let vlen = (info & 0xffff) as usize;
+
+            let extra = match kind {
+                // INT, VAR, DECL_TAG
+                1 | 14 | 17 => 4,
+                // ARRAY
+                3 => 12,
+                // STRUCT, UNION, DATASEC, ENUM64
+                4 | 5 | 15 | 19 => vlen * 12,
+                // ENUM, FUNC_PROTO
+                6 | 13 => vlen * 8,
+                // PTR, FWD, TYPEDEF, VOLATILE, CONST, RESTRICT, FUNC, FLOAT, TYPE_TAG
+                2 | 7..=12 | 16 | 18 => 0,
+                _ => bail!("Unknown BTF kind {}", kind),
+            };
+
+            name_offsets.push(name_off);
+            off += 12 + extra;
+        }
+
+        Ok(Btf { name_offsets, strings })
+    }
+
+    fn type_name(&self, type_id: u32) -> Option<&str> {
+        let name_off = *self.name_offsets.get((type_id as usize).checked_sub(1)?)? as usize;
+        let rest = self.strings.get(name_off..)?;
+        let end = rest.iter().position(|&b| b == 0)?;
+        std::str::from_utf8(&rest[..end]).ok()
+    }
+}
+
+/// enum bpf_link_type values that get named
+const BPF_LINK_TYPES: &[(u32, &str)] = &[
+    (1, "raw_tracepoint"),
+    (2, "tracing"),
+    (3, "cgroup"),
+    (4, "iter"),
+    (5, "netns"),
+    (6, "xdp"),
+    (7, "perf_event"),
+    (8, "kprobe_multi"),
+    (9, "struct_ops"),
+    (10, "netfilter"),
+    (11, "tcx"),
+    (12, "uprobe_multi"),
+    (13, "netkit"),
+    (14, "sockmap"),
+];
+
+const BPF_LINK_TYPE_TRACING: u32 = 2;
+const BPF_LINK_TYPE_XDP: u32 = 6;
+
+/// what a link attaches its program to
+pub enum LinkTarget {
+    /// XDP link on an interface
+    Xdp { ifindex: u32 },
+    /// fentry/fexit/freplace link, for freplace the target is a BPF program
+    /// and `target_btf_id` the function it replaces in that program's BTF
+    Tracing { target_obj_id: u32, target_btf_id: u32 },
+    Other { link_type: u32 },
+}
+
+pub struct LinkInfo {
+    pub id: u32,
+    pub prog_id: u32,
+    pub target: LinkTarget,
+}
+
+impl LinkInfo {
+    pub fn type_name(&self) -> &'static str {
+        let link_type = match self.target {
+            LinkTarget::Xdp { .. } => BPF_LINK_TYPE_XDP,
+            LinkTarget::Tracing { .. } => BPF_LINK_TYPE_TRACING,
+            LinkTarget::Other { link_type } => link_type,
+        };
+        BPF_LINK_TYPES
+            .iter()
+            .find(|(t, _)| *t == link_type)
+            .map_or("unknown", |(_, name)| name)
+    }
+}
+
+/// every BPF link in the system, walked with BPF_LINK_GET_NEXT_ID (5.8+)
+pub fn links() -> Result<Vec<LinkInfo>> {
+    let mut links = Vec::new();
+    let mut next = 0;
+
+    loop {
+        let mut attr = GetFdByIdAttr { id: next, ..Default::default() };
+        match sys_bpf(BPF_LINK_GET_NEXT_ID, &mut attr) {
+            Ok(_) => next = attr.next_id,
+            Err(Errno::ENOENT) => break,
+            Err(e) => bail!("BPF_LINK_GET_NEXT_ID failed: {}", errno_text(e)),
+        }
+
+        let mut attr = GetFdByIdAttr { id: next, ..Default::default() };
+        let fd = match sys_bpf(BPF_LINK_GET_FD_BY_ID, &mut attr) {
+            Ok(fd) => unsafe { OwnedFd::from_raw_fd(fd) },
+            // detached between the two calls
+            Err(Errno::ENOENT) => continue,
+            Err(e) => bail!("BPF_LINK_GET_FD_BY_ID {} failed: {}", next, errno_text(e)),
+        };
+
+        let mut info = RawLinkInfo::default();
+        obj_info(fd.as_fd(), &mut info).map_err(|e| anyhow!("Reading link {} failed: {}", next, errno_text(e)))?;
+
+        let target = match info.link_type {
+            BPF_LINK_TYPE_XDP => LinkTarget::Xdp { ifindex: info.data[0] },
+            // tracing: attach_type, target_obj_id, target_btf_id
+            BPF_LINK_TYPE_TRACING => LinkTarget::Tracing {
+                target_obj_id: info.data[1],
+                target_btf_id: info.data[2],
+            },
+            link_type => LinkTarget::Other { link_type },
+        };
+
+        links.push(LinkInfo {
+            id: info.id,
+            prog_id: info.prog_id,
+            target,
+        });
+    }
+
+    Ok(links)
+}
+
+/// what a bpffs pin refers to
+pub enum PinnedObject {
+    Prog { id: u32 },
+    Map { id: u32 },
+    Link { id: u32, prog_id: u32 },
+}
+
+/// open a pin with BPF_OBJ_GET and identify the object behind it from the
+/// fd's anon inode name and fdinfo
+pub fn pinned_object(path: &Path) -> Result<PinnedObject> {
+    let pathname = CString::new(path.as_os_str().as_bytes())?;
+    let mut attr = ObjGetAttr {
+        pathname: pathname.as_ptr() as u64,
+        ..Default::default()
+    };
+    let fd = sys_bpf(BPF_OBJ_GET, &mut attr)
+        .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+        .map_err(|e| anyhow!("BPF_OBJ_GET failed: {}", errno_text(e)))?;
+
+    let raw = fd.as_raw_fd();
+    let kind = fs::read_link(format!("/proc/self/fd/{}", raw))?;
+    let fdinfo = fs::read_to_string(format!("/proc/self/fdinfo/{}", raw))?;
+    let field = |key: &str| -> Result<u32> {
+        fdinfo
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse().ok())
+            .ok_or_else(|| anyhow!("No {} in fdinfo", key))
+    };
+
+    match kind.to_string_lossy().as_ref() {
+        "anon_inode:bpf-prog" => Ok(PinnedObject::Prog { id: field("prog_id")? }),
+        "anon_inode:bpf-map" => Ok(PinnedObject::Map { id: field("map_id")? }),
+        "anon_inode:bpf_link" | "anon_inode:bpf-link" => Ok(PinnedObject::Link {
+            id: field("link_id")?,
+            prog_id: field("prog_id")?,
+        }),
+        other => bail!("Unknown BPF object type {}", other),
+    }
+}
+
+/// value stored under `key` in the map with this ID
+pub fn map_lookup(map_id: u32, key: &[u8], value_size: usize) -> Result<Vec<u8>> {
+    let mut attr = GetFdByIdAttr { id: map_id, ..Default::default() };
+    let fd = sys_bpf(BPF_MAP_GET_FD_BY_ID, &mut attr)
+        .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+        .map_err(|e| anyhow!("BPF_MAP_GET_FD_BY_ID {} failed: {}", map_id, errno_text(e)))?;
+
+    let mut value = vec![0u8; value_size];
+    let mut attr = MapElemAttr {
+        map_fd: fd.as_raw_fd() as u32,
+        key: key.as_ptr() as u64,
+        value: value.as_mut_ptr() as u64,
+        ..Default::default()
+    };
+    sys_bpf(BPF_MAP_LOOKUP_ELEM, &mut attr)
+        .map_err(|e| anyhow!("Lookup in map {} failed: {}", map_id, errno_text(e)))?;
+
+    Ok(value)
+}
+
+/// name of a type in the BTF object with this ID
+pub fn btf_type_name(btf_id: u32, type_id: u32) -> Result<String> {
+    let btf = Btf::from_id(btf_id)?;
+    btf.type_name(type_id)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("BTF {} has no type {}", btf_id, type_id))
+}
+
+fn create_map(map_type: u32, key_size: u32, value_size: u32, max_entries: u32) -> Result<OwnedFd, Errno> {
+    let mut attr = MapCreateAttr {
+        map_type,
+        key_size,
+        value_size,
+        max_entries,
+        ..Default::default()
+    };
+    sys_bpf(BPF_MAP_CREATE, &mut attr).map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// load an XDP program, with `verifier_log` the log comes back alongside the errno
+fn load_xdp(insns: &[Insn], prog_flags: u32, verifier_log: bool) -> Result<OwnedFd, (Errno, String)> {
+    let mut log = vec![0u8; if verifier_log { 4096 } else { 0 }];
+    let mut attr = ProgLoadAttr {
+        prog_type: BPF_PROG_TYPE_XDP,
+        insn_cnt: insns.len() as u32,
+        insns: insns.as_ptr() as u64,
+        license: LICENSE.as_ptr() as u64,
+        prog_flags,
+        ..Default::default()
+    };
+
+    if verifier_log {
+        attr.log_level = 1;
+        attr.log_size = log.len() as u32;
+        attr.log_buf = log.as_mut_ptr() as u64;
+    }
+
+    match sys_bpf(BPF_PROG_LOAD, &mut attr) {
+        Ok(fd) => Ok(unsafe { OwnedFd::from_raw_fd(fd) }),
+        Err(errno) => {
+            let end = log.iter().position(|&b| b == 0).unwrap_or(log.len());
+            Err((errno, String::from_utf8_lossy(&log[..end]).into_owned()))
+        }
+    }
+}
+
+/// runtime feature probes via bpf(2), in the spirit of `bpftool feature probe`
+pub fn probe_features() -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let xdp = load_xdp(&[MOV_R0_XDP_PASS, EXIT], 0, false).map(drop).map_err(|(errno, _)| errno);
+    let privileged = xdp != Err(Errno::EPERM);
+    results.push(feature_result("BPF: XDP Program", "XDP program load", xdp, true));
+
+    if !privileged {
+        // every other probe would fail the same way and say nothing about the kernel
+        return results;
+    }
+
+    let page = nix::unistd::sysconf(nix::unistd::SysconfVar::PAGE_SIZE)
+        .ok()
+        .flatten()
+        .unwrap_or(4096) as u32;
+
+    for (name, description, map_type, key, value, entries, required) in [
+        ("BPF: XSKMAP", "BPF_MAP_TYPE_XSKMAP", BPF_MAP_TYPE_XSKMAP, 4, 4, 1, true),
+        ("BPF: DEVMAP", "BPF_MAP_TYPE_DEVMAP", BPF_MAP_TYPE_DEVMAP, 4, 4, 1, false),
+        ("BPF: DEVMAP_HASH", "BPF_MAP_TYPE_DEVMAP_HASH", BPF_MAP_TYPE_DEVMAP_HASH, 4, 4, 1, false),
+        ("BPF: CPUMAP", "BPF_MAP_TYPE_CPUMAP", BPF_MAP_TYPE_CPUMAP, 4, 4, 1, false),
+        ("BPF: Ring Buffer", "BPF_MAP_TYPE_RINGBUF", BPF_MAP_TYPE_RINGBUF, 0, 0, page, false),
+    ] {
+        let outcome = create_map(map_type, key, value, entries).map(drop);
+        results.push(feature_result(name, description, outcome, required));
+    }
+
+    for (name, helper, id, required) in [
+        ("BPF: bpf_redirect_map", "bpf_redirect_map helper", BPF_FUNC_REDIRECT_MAP, true),
+        ("BPF: bpf_xdp_adjust_tail", "bpf_xdp_adjust_tail helper", BPF_FUNC_XDP_ADJUST_TAIL, false),
+    ] {
+        results.push(probe_helper(name, helper, id, required));
+    }
+
+    let frags = load_xdp(&[MOV_R0_XDP_PASS, EXIT], BPF_F_XDP_HAS_FRAGS, false)
+        .map(drop)
+        .map_err(|(errno, _)| errno);
+    results.push(feature_result("BPF: XDP Frags", "BPF_F_XDP_HAS_FRAGS (multi-buffer XDP)", frags, false));
+
+    results
+}
+
+/// a program that only calls the helper, with garbage arguments. it never
+/// verifies, so support is judged from the log like bpftool does.
+fn probe_helper(name: &str, helper: &str, id: i32, required: bool) -> CheckResult {
+    let outcome = match load_xdp(&[call(id), MOV_R0_XDP_PASS, EXIT], 0, true) {
+        Ok(_) => Ok(()),
+        Err((errno, log)) if log.contains("invalid func ") || log.contains("unknown func ") => Err(errno),
+        // no log means the load failed before the verifier ran
+        Err((errno, log)) if log.is_empty() => Err(errno),
+        // rejected for the garbage arguments, so the helper itself exists
+        Err(_) => Ok(()),
+    };
+
+    feature_result(name, helper, outcome, required)
+}
+
+fn feature_result(name: &str, description: &str, outcome: Result<(), Errno>, required: bool) -> CheckResult {
+    match outcome {
+        Ok(()) => CheckResult {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            message: format!("{} supported", description),
+            details: None,
+        },
+        Err(Errno::EPERM) => CheckResult {
+            name: name.to_string(),
+            status: CheckStatus::Error,
+            message: format!("Cannot probe {}: {}", description, errno_text(Errno::EPERM)),
+            details: Some("Run as root or with CAP_BPF and CAP_NET_ADMIN".to_string()),
+        },
+        Err(errno) => CheckResult {
+            name: name.to_string(),
+            status: if required { CheckStatus::Fail } else { CheckStatus::Warning },
+            message: format!("{} not supported: {}", description, errno_text(errno)),
+            details: Some(if required {
+                "Required for AF_XDP".to_string()
+            } else {
+                "Optional, some XDP deployments need it".to_string()
+            }),
+        },
+    }
+}
@@ -0,0 +1,362 @@
+use anyhow::{Context, Result};
+use aya::maps::MapInfo;
+use aya::programs::{loaded_programs, ProgramInfo, ProgramType};
+use nix::mount::{mount, MsFlags};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::bpf::{self, LinkInfo, LinkTarget, PinnedObject};
+use crate::netlink;
+use crate::output::{CheckResult, CheckStatus};
+
+/// where libbpf, iproute2 and most loaders pin by default
+const BPFFS_DEFAULT: &str = "/sys/fs/bpf";
+
+/// one bpffs line of /proc/self/mountinfo
+pub struct BpffsMount {
+    pub mount_point: PathBuf,
+    /// permission bits of the root directory, from the mode= option
+    pub mode: u32,
+    /// "shared:N", "master:N", "unbindable", or "private" without any
+    pub propagation: Vec<String>,
+    pub read_only: bool,
+}
+
+impl BpffsMount {
+    fn propagation(&self) -> String {
+        if self.propagation.is_empty() {
+            "private".to_string()
+        } else {
+            self.propagation.join(" ")
+        }
+    }
+}
+
+/// every bpffs instance in /proc/self/mountinfo, see proc(5) for the format
+pub fn bpffs_mounts() -> Vec<BpffsMount> {
+    let Ok(mountinfo) = fs::read_to_string("/proc/self/mountinfo") else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    mountinfo
+        .lines()
+        .filter_map(parse_mountinfo)
+        .filter(|m| seen.insert(m.mount_point.clone()))
+        .collect()
+}
+
+/// "44 43 0:39 / /sys/fs/bpf rw,relatime shared:5 - bpf bpf rw,mode=700"
+fn parse_mountinfo(line: &str) -> Option<BpffsMount> {
+    let (mount, fs) = line.split_once(" - ")?;
+
+    let mut fs_fields = fs.split_whitespace();
+    if fs_fields.next()? != "bpf" {
+        return None;
+    }
+    let super_options = fs_fields.nth(1).unwrap_or("");
+
+    let mut fields = mount.split_whitespace();
+    let mount_point = fields.nth(4)?;
+    let mount_options = fields.next()?;
+    let propagation = fields.map(str::to_string).collect();
+
+    // bpffs only shows mode= when it differs from the default 0777
+    let mode = super_options
+        .split(',')
+        .find_map(|option| option.strip_prefix("mode="))
+        .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+        .unwrap_or(0o777);
+
+    Some(BpffsMount {
+        mount_point: PathBuf::from(unescape_mount(mount_point)),
+        mode,
+        propagation,
+        read_only: mount_options.split(',').any(|o| o == "ro"),
+    })
+}
+
+/// mountinfo escapes space, tab, newline and backslash as octal
+fn unescape_mount(field: &str) -> String {
+    field
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\012", "\n")
+        .replace("\\134", "\\")
+}
+
+/// mount a bpffs at /sys/fs/bpf the way systemd does
+fn mount_default() -> Result<()> {
+    mount(
+        Some("bpf"),
+        BPFFS_DEFAULT,
+        Some("bpf"),
+        MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+        Some("mode=700"),
+    )
+    .with_context(|| format!("mount -t bpf bpf {} failed", BPFFS_DEFAULT))
+}
+
+/// every bpffs mount with its mode and propagation. with `fix` a missing
+/// /sys/fs/bpf is mounted first.
+pub fn check_mounts(fix: bool) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let mut mounts = bpffs_mounts();
+    let has_default = |mounts: &[BpffsMount]| mounts.iter().any(|m| m.mount_point == Path::new(BPFFS_DEFAULT));
+
+    if !has_default(&mounts) && fix {
+        match mount_default() {
+            Ok(()) => {
+                results.push(CheckResult {
+                    name: "BPF Filesystem".to_string(),
+                    status: CheckStatus::Pass,
+                    message: format!("Mounted bpffs at {}", BPFFS_DEFAULT),
+                    details: Some("Not persistent; add it to /etc/fstab: bpf /sys/fs/bpf bpf defaults,mode=700 0 0".to_string()),
+                });
+                mounts = bpffs_mounts();
+            }
+            Err(e) => results.push(CheckResult {
+                name: "BPF Filesystem".to_string(),
+                status: CheckStatus::Error,
+                message: format!("Unable to mount bpffs at {}", BPFFS_DEFAULT),
+                details: Some(format!("{:#}", e)),
+            }),
+        }
+    }
+
+    if !has_default(&mounts) {
+        results.push(CheckResult {
+            name: "BPF Filesystem".to_string(),
+            status: CheckStatus::Warning,
+            message: format!("No bpffs mounted at {}", BPFFS_DEFAULT),
+            details: Some(format!(
+                "Loaders that pin programs, maps or links fail without it\nMount with: mount -t bpf bpf {}, or rerun with --fix",
+                BPFFS_DEFAULT
+            )),
+        });
+    }
+
+    for m in &mounts {
+        results.push(CheckResult {
+            name: format!("bpffs {}", m.mount_point.display()),
+            status: if m.read_only { CheckStatus::Warning } else { CheckStatus::Pass },
+            message: format!(
+                "Mounted{}, mode {:04o}, {}",
+                if m.read_only { " read-only" } else { "" },
+                m.mode,
+                m.propagation()
+            ),
+            details: Some(if m.propagation.is_empty() {
+                "Private mount: pins made here are not visible in other mount namespaces (containers)".to_string()
+            } else {
+                "Pins propagate to mount namespaces that share this mount".to_string()
+            }),
+        });
+    }
+
+    results
+}
+
+/// every regular file below `dir`, symlinks are not followed
+fn walk(dir: &Path, pins: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            walk(&entry.path(), pins);
+        } else if file_type.is_file() {
+            pins.push(entry.path());
+        }
+    }
+}
+
+/// programs something is running: attached XDP programs, programs held by a
+/// link whose target still exists
+fn attached_programs(links: &[LinkInfo]) -> HashSet<u32> {
+    let mut attached: HashSet<u32> = netlink::xdp_attachments()
+        .map(|attachments| {
+            attachments
+                .iter()
+                .flat_map(|a| a.programs())
+                .map(|(_, id)| id)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    attached.extend(
+        links
+            .iter()
+            .filter(|l| !matches!(l.target, LinkTarget::Xdp { ifindex: 0 }))
+            .map(|l| l.prog_id),
+    );
+
+    attached
+}
+
+/// every pin on every bpffs mount, identified with BPF_OBJ_GET
+pub fn check_pins() -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let links = bpf::links().unwrap_or_default();
+    let attached = attached_programs(&links);
+    let programs: HashMap<u32, ProgramInfo> = loaded_programs()
+        .filter_map(|r| r.ok())
+        .map(|p| (p.id(), p))
+        .collect();
+
+    // bind mounts show the same bpffs under another path, walk it once
+    let mut walked: HashMap<(u64, u64), PathBuf> = HashMap::new();
+
+    for mount in bpffs_mounts() {
+        let mount = mount.mount_point;
+        if let Ok(meta) = fs::metadata(&mount) {
+            if let Some(first) = walked.get(&(meta.dev(), meta.ino())) {
+                results.push(CheckResult {
+                    name: format!("bpffs {}: Pins", mount.display()),
+                    status: CheckStatus::Info,
+                    message: format!("Same bpffs as {}, pins listed there", first.display()),
+                    details: None,
+                });
+                continue;
+            }
+            walked.insert((meta.dev(), meta.ino()), mount.clone());
+        }
+
+        let mut pins = Vec::new();
+        walk(&mount, &mut pins);
+        pins.sort();
+
+        let (mut progs, mut maps, mut link_count) = (0, 0, 0);
+
+        for pin in &pins {
+            let object = match bpf::pinned_object(pin) {
+                Ok(object) => object,
+                Err(e) => {
+                    // bpf_preload's progs.debug and maps.debug are plain files
+                    log::debug!("{}: {:#}", pin.display(), e);
+                    continue;
+                }
+            };
+
+            let name = format!("Pin {}", pin.display());
+            results.push(match object {
+                PinnedObject::Prog { id } => {
+                    progs += 1;
+                    pinned_prog(name, id, programs.get(&id), &attached)
+                }
+                PinnedObject::Map { id } => {
+                    maps += 1;
+                    pinned_map(name, id)
+                }
+                PinnedObject::Link { id, prog_id } => {
+                    link_count += 1;
+                    pinned_link(name, id, prog_id, programs.get(&prog_id), &links)
+                }
+            });
+        }
+
+        results.push(CheckResult {
+            name: format!("bpffs {}: Pins", mount.display()),
+            status: CheckStatus::Info,
+            message: format!("{} prog(s), {} map(s), {} link(s) pinned", progs, maps, link_count),
+            details: None,
+        });
+    }
+
+    results
+}
+
+fn prog_label(id: u32, prog: Option<&ProgramInfo>) -> String {
+    match prog {
+        Some(prog) => format!(
+            "prog {} (ID: {}, {})",
+            prog.name_as_str().filter(|n| !n.is_empty()).unwrap_or("(unnamed)"),
+            id,
+            prog.program_type().map_or("unknown".to_string(), |t| format!("{:?}", t))
+        ),
+        None => format!("prog (ID: {})", id),
+    }
+}
+
+fn pinned_prog(name: String, id: u32, prog: Option<&ProgramInfo>, attached: &HashSet<u32>) -> CheckResult {
+    let label = prog_label(id, prog);
+
+    // only XDP and freplace attachments can be seen from here
+    let checkable = prog.is_some_and(|p| matches!(p.program_type(), Ok(ProgramType::Xdp | ProgramType::Extension)));
+
+    if attached.contains(&id) {
+        CheckResult {
+            name,
+            status: CheckStatus::Info,
+            message: format!("{}, attached", label),
+            details: None,
+        }
+    } else if checkable {
+        CheckResult {
+            name,
+            status: CheckStatus::Warning,
+            message: format!("{}, dangling: not attached anywhere", label),
+            details: Some("The pin keeps the program loaded. Remove it if no loader will reattach it".to_string()),
+        }
+    } else {
+        CheckResult {
+            name,
+            status: CheckStatus::Info,
+            message: label,
+            details: Some("Attachment not checked for this program type".to_string()),
+        }
+    }
+}
+
+fn pinned_map(name: String, id: u32) -> CheckResult {
+    let message = match MapInfo::from_id(id) {
+        Ok(map) => format!(
+            "map {} (ID: {}, {})",
+            map.name_as_str().filter(|n| !n.is_empty()).unwrap_or("(unnamed)"),
+            id,
+            map.map_type().map_or("unknown".to_string(), |t| format!("{:?}", t))
+        ),
+        Err(_) => format!("map (ID: {})", id),
+    };
+
+    CheckResult {
+        name,
+        status: CheckStatus::Info,
+        message,
+        details: None,
+    }
+}
+
+fn pinned_link(name: String, id: u32, prog_id: u32, prog: Option<&ProgramInfo>, links: &[LinkInfo]) -> CheckResult {
+    let link = links.iter().find(|l| l.id == id);
+    let kind = link.map_or("unknown", |l| l.type_name());
+    let message = format!("{} link (ID: {}) for {}", kind, id, prog_label(prog_id, prog));
+
+    match link.map(|l| &l.target) {
+        // the interface is gone, only the pin holds the link
+        Some(LinkTarget::Xdp { ifindex: 0 }) => CheckResult {
+            name,
+            status: CheckStatus::Warning,
+            message: format!("{}, dangling: interface removed", message),
+            details: Some("Remove the pin to release the program".to_string()),
+        },
+        Some(LinkTarget::Xdp { ifindex }) => CheckResult {
+            name,
+            status: CheckStatus::Info,
+            message,
+            details: Some(format!("Ifindex: {}", ifindex)),
+        },
+        _ => CheckResult {
+            name,
+            status: CheckStatus::Info,
+            message,
+            details: None,
+        },
+    }
+}
@@ -1,8 +1,8 @@
 use anyhow::Result;
 use caps::{CapSet, Capability};
 use nix::unistd::geteuid;
-use nix::sys::utsname;
 
+use crate::kernel::KernelVersion;
 use crate::output::{CheckResult, CheckStatus};
 
 pub fn check_capabilities() -> Result<Vec<CheckResult>> {
@@ -26,13 +26,18 @@ pub fn check_capabilities() -> Result<Vec<CheckResult>> {
     });
 
     // check kernel version to determine which capabilities model to use
-    let uname = utsname::uname()?;
-    let release = uname.release().to_str().unwrap_or("unknown");
-    let parts: Vec<&str> = release.split(&['.', '-'][..]).collect();
-    let major: u32 = if parts.len() > 0 { parts[0].parse().unwrap_or(0) } else { 0 };
-    let minor: u32 = if parts.len() > 1 { parts[1].parse().unwrap_or(0) } else { 0 };
-
-    let kernel_5_8_plus = major > 5 || (major == 5 && minor >= 8);
+    let kernel_5_8_plus = match KernelVersion::running() {
+        Ok(kernel) => kernel.at_least(5, 8),
+        Err(e) => {
+            results.push(CheckResult {
+                name: "Capability Model".to_string(),
+                status: CheckStatus::Warning,
+                message: "Unable to determine the kernel version".to_string(),
+                details: Some(format!("{:#}\nAssuming CAP_BPF and CAP_PERFMON (5.8+)", e)),
+            });
+            true
+        }
+    };
 
     let mut required_caps = vec![
         (Capability::CAP_NET_RAW, "Raw socket operations"),
@@ -0,0 +1,220 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::kernel::KernelVersion;
+
+/// knowledge base shipped with the binary, see data/drivers.toml
+const EMBEDDED_DB: &str = include_str!("../data/drivers.toml");
+
+/// schema version this build understands
+const DB_VERSION: u32 = 1;
+
+static DRIVER_DB: OnceLock<DriverDb> = OnceLock::new();
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DriverDb {
+    pub version: u32,
+    #[serde(default, rename = "driver")]
+    pub drivers: Vec<DriverInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DriverInfo {
+    pub name: String,
+    pub description: Option<String>,
+    /// first kernel with native XDP in this driver
+    pub native_xdp: Option<String>,
+    /// first kernel with AF_XDP zero-copy
+    pub zero_copy: Option<String>,
+    /// first kernel with XDP multi-buffer (frags)
+    pub multi_buffer: Option<String>,
+    /// largest MTU native XDP takes with one page per frame, i.e. without multi-buffer
+    pub xdp_max_mtu: Option<u32>,
+    pub recommended_firmware: Option<String>,
+    #[serde(default)]
+    pub known_bugs: Vec<KnownBug>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownBug {
+    pub description: String,
+    /// affected kernels, all of them when absent
+    pub kernels: Option<VersionRange>,
+    /// affected driver versions (ETHTOOL_GDRVINFO), all of them when absent
+    pub driver_versions: Option<VersionRange>,
+}
+
+impl KnownBug {
+    /// whether the bug hits this host, an unknown version counts as affected
+    pub fn applies(&self, kernel: Option<&KernelVersion>, driver_version: Option<&str>) -> bool {
+        let in_range = |range: &Option<VersionRange>, version: Option<(u32, u32)>| match (range, version) {
+            (Some(range), Some(version)) => range.contains(version),
+            _ => true,
+        };
+
+        in_range(&self.kernels, kernel.map(|k| (k.major, k.minor)))
+            && in_range(&self.driver_versions, driver_version.and_then(parse_version))
+    }
+
+    pub fn describe(&self) -> String {
+        let mut text = self.description.clone();
+        if let Some(range) = &self.kernels {
+            text.push_str(&format!(" (kernels: {})", range.describe()));
+        }
+        if let Some(range) = &self.driver_versions {
+            text.push_str(&format!(" (driver versions: {})", range.describe()));
+        }
+        text
+    }
+}
+
+/// half-open version range, `from <= v < to`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl VersionRange {
+    pub fn contains(&self, version: (u32, u32)) -> bool {
+        let bound = |v: &Option<String>| v.as_deref().and_then(parse_version);
+
+        bound(&self.from).is_none_or(|from| version >= from)
+            && bound(&self.to).is_none_or(|to| version < to)
+    }
+
+    pub fn describe(&self) -> String {
+        match (&self.from, &self.to) {
+            (Some(from), Some(to)) => format!("{} to before {}", from, to),
+            (Some(from), None) => format!("{} and later", from),
+            (None, Some(to)) => format!("before {}", to),
+            (None, None) => "all".to_string(),
+        }
+    }
+}
+
+impl DriverDb {
+    fn parse(content: &str, source: &str) -> Result<Self> {
+        let db: DriverDb = toml::from_str(content)
+            .with_context(|| format!("Failed to parse driver database {}", source))?;
+
+        if db.version != DB_VERSION {
+            bail!(
+                "Driver database {} has version {}, this build understands version {}",
+                source,
+                db.version,
+                DB_VERSION
+            );
+        }
+
+        // catch typos at load time rather than silently never matching
+        for driver in &db.drivers {
+            let mut versions = vec![&driver.native_xdp, &driver.zero_copy, &driver.multi_buffer];
+            for bug in &driver.known_bugs {
+                for range in [&bug.kernels, &bug.driver_versions].into_iter().flatten() {
+                    versions.push(&range.from);
+                    versions.push(&range.to);
+                }
+            }
+
+            for version in versions.into_iter().flatten() {
+                if parse_version(version).is_none() {
+                    bail!("Driver database {}: invalid version '{}' for {}", source, version, driver.name);
+                }
+            }
+        }
+
+        Ok(db)
+    }
+
+    pub fn get(&self, driver: &str) -> Option<&DriverInfo> {
+        self.drivers.iter().find(|d| d.name == driver)
+    }
+}
+
+impl DriverInfo {
+    /// native XDP on `kernel`, or at all when the kernel version is unknown
+    pub fn supports_native(&self, kernel: Option<&KernelVersion>) -> bool {
+        since(&self.native_xdp, kernel)
+    }
+
+    /// XDP multi-buffer (frags) on `kernel`, or at all when the kernel version is unknown
+    pub fn supports_multi_buffer(&self, kernel: Option<&KernelVersion>) -> bool {
+        since(&self.multi_buffer, kernel)
+    }
+
+    pub fn details(&self) -> String {
+        let version = |v: &Option<String>| v.clone().unwrap_or_else(|| "not supported".to_string());
+
+        let mut lines = Vec::new();
+        if let Some(description) = &self.description {
+            lines.push(description.clone());
+        }
+        lines.push(format!(
+            "Min kernel: native XDP {}, zero-copy {}, multi-buffer {}",
+            version(&self.native_xdp),
+            version(&self.zero_copy),
+            version(&self.multi_buffer)
+        ));
+        if let Some(mtu) = self.xdp_max_mtu {
+            lines.push(format!("Single-buffer XDP MTU: {}", mtu));
+        }
+        if let Some(firmware) = &self.recommended_firmware {
+            lines.push(format!("Recommended firmware: {}", firmware));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// whether `kernel` is at least the first kernel `min` with a feature
+fn since(min: &Option<String>, kernel: Option<&KernelVersion>) -> bool {
+    match (min, kernel) {
+        (Some(min), Some(kernel)) => parse_version(min).is_some_and(|(major, minor)| kernel.at_least(major, minor)),
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// "major.minor[.patch][-suffix]" -> (major, minor), e.g. "2.25.7-k" -> (2, 25).
+/// for database entries and driver versions, the running kernel is a `KernelVersion`
+pub fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = match parts.next() {
+        Some(minor) => minor.parse().ok()?,
+        None => 0,
+    };
+    Some((major, minor))
+}
+
+/// load the database from `path`, or the embedded copy. call once before any check.
+pub fn init(path: Option<&Path>) -> Result<()> {
+    let db = match path {
+        Some(path) => {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read driver database {}", path.display()))?;
+            DriverDb::parse(&content, &path.display().to_string())?
+        }
+        None => DriverDb::parse(EMBEDDED_DB, "(embedded)")?,
+    };
+
+    log::debug!("Loaded driver database with {} driver(s)", db.drivers.len());
+
+    DRIVER_DB
+        .set(db)
+        .map_err(|_| anyhow::anyhow!("Driver database already initialized"))
+}
+
+pub fn db() -> &'static DriverDb {
+    DRIVER_DB.get_or_init(|| {
+        DriverDb::parse(EMBEDDED_DB, "(embedded)").expect("embedded driver database is valid")
+    })
+}
@@ -0,0 +1,220 @@
+use anyhow::{anyhow, bail, Context, Result};
+use object::{Object, ObjectSection, ObjectSymbol, RelocationTarget, SymbolKind};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+
+use crate::kernel::KernelVersion;
+
+const INSN_SIZE: usize = 8;
+
+/// BPF_LD | BPF_IMM | BPF_DW, the first half of a 16 byte ld_imm64
+const LD_IMM64: u8 = 0x18;
+/// BPF_JMP | BPF_CALL
+const CALL: u8 = 0x85;
+
+/// magic of the .BTF and .BTF.ext headers
+const BTF_MAGIC: u16 = 0xeb9f;
+/// btf_ext_header up to core_relo_len, older objects end after line_info_len
+const BTF_EXT_CORE_HDR_LEN: usize = 32;
+
+/// src_reg values the loader puts into ld_imm64 for map references
+const BPF_PSEUDO_MAP_FD: u8 = 1;
+const BPF_PSEUDO_MAP_VALUE: u8 = 2;
+/// src_reg of a call to another BPF function
+const BPF_PSEUDO_CALL: u8 = 1;
+
+/// tag the kernel will report for `program` from the ELF object at `path`.
+///
+/// reproduces what the loader hands to BPF_PROG_LOAD (map references become
+/// pseudo map loads) and then the kernel's bpf_prog_calc_tag(). programs that
+/// call other BPF functions or need CO-RE relocation are rewritten by the
+/// loader in ways that cannot be replayed here, those are rejected.
+pub fn program_tag(path: &Path, program: &str) -> Result<u64> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let file = object::File::parse(&*data)
+        .with_context(|| format!("{} is not a valid ELF object", path.display()))?;
+
+    let symbol = file
+        .symbols()
+        .find(|s| s.kind() == SymbolKind::Text && s.name() == Ok(program))
+        .ok_or_else(|| anyhow!("No program '{}' in {}", program, path.display()))?;
+    let section = symbol
+        .section_index()
+        .and_then(|index| file.section_by_index(index).ok())
+        .ok_or_else(|| anyhow!("Program '{}' has no section", program))?;
+
+    let section_data = section.data()?;
+    let start = symbol.address() as usize;
+    let end = match symbol.size() as usize {
+        // hand written objects may leave st_size unset
+        0 => section_data.len(),
+        size => start + size,
+    };
+    let mut insns = section_data
+        .get(start..end)
+        .ok_or_else(|| anyhow!("Program '{}' lies outside its section", program))?
+        .to_vec();
+
+    for (offset, relocation) in section.relocations() {
+        let offset = offset as usize;
+        if offset < start || offset >= end {
+            continue;
+        }
+        let insn = offset - start;
+
+        let RelocationTarget::Symbol(index) = relocation.target() else {
+            continue;
+        };
+        let target_section = file
+            .symbol_by_index(index)
+            .ok()
+            .and_then(|s| s.section_index())
+            .and_then(|i| file.section_by_index(i).ok());
+        let target = target_section.as_ref().and_then(|s| s.name().ok()).unwrap_or("");
+
+        if insns[insn] != LD_IMM64 {
+            bail!(
+                "Program '{}' calls other BPF functions ({}), its tag cannot be computed from the object",
+                program,
+                target
+            );
+        }
+
+        let src_reg = if target.starts_with("maps") || target == ".maps" {
+            BPF_PSEUDO_MAP_FD
+        } else if [".data", ".rodata", ".bss"].iter().any(|p| target.starts_with(p)) {
+            BPF_PSEUDO_MAP_VALUE
+        } else {
+            bail!("Unsupported relocation against section '{}' in '{}'", target, program);
+        };
+        insns[insn + 1] = (insns[insn + 1] & 0x0f) | (src_reg << 4);
+    }
+
+    let core_relos = core_relocations(&file, section.name()?, start..end)?;
+    if core_relos > 0 {
+        bail!(
+            "Program '{}' has {} CO-RE relocation(s), the loader rewrites them for the running kernel so its tag cannot be computed from the object",
+            program,
+            core_relos
+        );
+    }
+
+    if insns
+        .chunks_exact(INSN_SIZE)
+        .any(|insn| insn[0] == CALL && insn[1] >> 4 == BPF_PSEUDO_CALL)
+    {
+        bail!(
+            "Program '{}' calls other BPF functions, its tag cannot be computed from the object",
+            program
+        );
+    }
+
+    Ok(calc_tag(&insns))
+}
+
+/// number of .BTF.ext core_relo records for instructions of `section` in
+/// `range`. the section names in .BTF.ext are offsets into the .BTF strings.
+fn core_relocations(file: &object::File, section: &str, range: std::ops::Range<usize>) -> Result<usize> {
+    let (Some(btf), Some(ext)) = (file.section_by_name(".BTF"), file.section_by_name(".BTF.ext")) else {
+        return Ok(0);
+    };
+    let (btf, ext) = (btf.data()?, ext.data()?);
+
+    let u32_at = |buf: &[u8], offset: usize| -> Result<usize> {
+        buf.get(offset..offset + 4)
+            .map(|b| u32::from_ne_bytes(b.try_into().unwrap()) as usize)
+            .ok_or_else(|| anyhow!("Truncated BTF at offset {}", offset))
+    };
+    let magic = |buf: &[u8]| buf.get(..2).map(|b| u16::from_ne_bytes([b[0], b[1]]));
+    if magic(btf) != Some(BTF_MAGIC) || magic(ext) != Some(BTF_MAGIC) {
+        bail!("Unsupported BTF byte order or magic in the object");
+    }
+
+    let ext_hdr_len = u32_at(ext, 4)?;
+    if ext_hdr_len < BTF_EXT_CORE_HDR_LEN {
+        return Ok(0);
+    }
+    let relo_start = ext_hdr_len + u32_at(ext, 24)?;
+    let relo_end = relo_start + u32_at(ext, 28)?;
+    if relo_end <= relo_start {
+        return Ok(0);
+    }
+
+    // struct btf_header: magic, version, flags, hdr_len, type_off, type_len, str_off, str_len
+    let strings_start = u32_at(btf, 4)? + u32_at(btf, 16)?;
+    let strings = btf
+        .get(strings_start..strings_start + u32_at(btf, 20)?)
+        .ok_or_else(|| anyhow!("Truncated BTF string section"))?;
+    let name_at = |offset: usize| {
+        strings
+            .get(offset..)
+            .and_then(|s| s.split(|&b| b == 0).next())
+            .map(String::from_utf8_lossy)
+    };
+
+    // u32 record_size, then per section: sec_name_off, num_info, records
+    // starting with the byte offset of the instruction (struct bpf_core_relo)
+    let record_size = u32_at(ext, relo_start)?;
+    let mut offset = relo_start + 4;
+    let mut count = 0;
+    while offset + 8 <= relo_end {
+        let name = name_at(u32_at(ext, offset)?);
+        let num_info = u32_at(ext, offset + 4)?;
+        offset += 8;
+        if name.as_deref() == Some(section) {
+            for record in 0..num_info {
+                let insn_off = u32_at(ext, offset + record * record_size)?;
+                if range.contains(&insn_off) {
+                    count += 1;
+                }
+            }
+        }
+        offset += num_info * record_size;
+    }
+
+    Ok(count)
+}
+
+/// kernel/bpf/core.c bpf_prog_calc_tag(): hash of the instructions with map
+/// fds and map value addresses zeroed, the first 8 bytes are the tag. 6.18
+/// switched the hash from SHA-1 to SHA-256 for program signing.
+fn calc_tag(insns: &[u8]) -> u64 {
+    let mut insns = insns.to_vec();
+
+    let mut was_ld_map = false;
+    for insn in insns.chunks_exact_mut(INSN_SIZE) {
+        let src_reg = insn[1] >> 4;
+        if !was_ld_map
+            && insn[0] == LD_IMM64
+            && (src_reg == BPF_PSEUDO_MAP_FD || src_reg == BPF_PSEUDO_MAP_VALUE)
+        {
+            was_ld_map = true;
+            insn[4..8].fill(0);
+        } else if was_ld_map && insn[..4] == [0, 0, 0, 0] {
+            // second half of the ld_imm64
+            was_ld_map = false;
+            insn[4..8].fill(0);
+        } else {
+            was_ld_map = false;
+        }
+    }
+
+    let sha256 = KernelVersion::running().is_ok_and(|k| k.at_least(6, 18));
+    let digest = if sha256 {
+        Sha256::digest(&insns).to_vec()
+    } else {
+        Sha1::digest(&insns).to_vec()
+    };
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+/// "0x1a2b..." or "1a2b..." as printed by bpftool and `prog`
+pub fn parse_tag(tag: &str) -> Result<u64> {
+    let hex = tag.trim().trim_start_matches("0x");
+    if hex.len() != 16 {
+        bail!("Program tag must be 16 hex digits, got '{}'", tag);
+    }
+    u64::from_str_radix(hex, 16).with_context(|| format!("Invalid program tag '{}'", tag))
+}
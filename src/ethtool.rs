@@ -0,0 +1,919 @@
+use anyhow::{anyhow, Result};
+use libc::{ifreq, ioctl, socket, AF_INET, IF_NAMESIZE, SIOCETHTOOL, SOCK_DGRAM};
+use std::collections::BTreeSet;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::OnceLock;
+use std::{mem, ptr};
+
+use crate::netlink::{self, attributes, ne_u32};
+
+// linux/ethtool.h, SIOCETHTOOL commands
+const ETHTOOL_GSET: u32 = 0x00000001;
+const ETHTOOL_GDRVINFO: u32 = 0x00000003;
+const ETHTOOL_GCOALESCE: u32 = 0x0000000e;
+const ETHTOOL_GRINGPARAM: u32 = 0x00000010;
+const ETHTOOL_GPAUSEPARAM: u32 = 0x00000012;
+const ETHTOOL_GSTRINGS: u32 = 0x0000001b;
+const ETHTOOL_GSTATS: u32 = 0x0000001d;
+const ETHTOOL_GRXFH: u32 = 0x00000029;
+const ETHTOOL_GSSET_INFO: u32 = 0x00000037;
+const ETHTOOL_GFEATURES: u32 = 0x0000003a;
+const ETHTOOL_GCHANNELS: u32 = 0x0000003c;
+const ETHTOOL_GRSSH: u32 = 0x00000046;
+
+/// string sets: ethtool -S names, private flag names, netdev feature names
+const ETH_SS_STATS: u32 = 1;
+const ETH_SS_FEATURES: u32 = 4;
+const ETH_GSTRING_LEN: usize = 32;
+
+/// ethtool_rxnfc flow_type for UDP over IPv4, what validators receive
+pub const UDP_V4_FLOW: u32 = 0x02;
+
+// linux/ethtool_netlink.h
+const ETHTOOL_GENL_VERSION: u8 = 1;
+const ETHTOOL_MSG_LINKINFO_GET: u8 = 2;
+const ETHTOOL_MSG_LINKMODES_GET: u8 = 4;
+const ETHTOOL_MSG_FEATURES_GET: u8 = 11;
+const ETHTOOL_MSG_RINGS_GET: u8 = 15;
+const ETHTOOL_MSG_CHANNELS_GET: u8 = 17;
+const ETHTOOL_MSG_COALESCE_GET: u8 = 19;
+const ETHTOOL_MSG_PAUSE_GET: u8 = 21;
+
+/// every request carries the device in a nested header, always attribute 1
+const ETHTOOL_A_HEADER: u16 = 1;
+const ETHTOOL_A_HEADER_DEV_NAME: u16 = 2;
+
+const ETHTOOL_A_BITSET_NOMASK: u16 = 1;
+const ETHTOOL_A_BITSET_BITS: u16 = 3;
+const ETHTOOL_A_BITSET_BITS_BIT: u16 = 1;
+const ETHTOOL_A_BITSET_BIT_NAME: u16 = 2;
+const ETHTOOL_A_BITSET_BIT_VALUE: u16 = 3;
+
+const ETHTOOL_A_LINKINFO_PORT: u16 = 2;
+
+const ETHTOOL_A_LINKMODES_AUTONEG: u16 = 2;
+const ETHTOOL_A_LINKMODES_OURS: u16 = 3;
+const ETHTOOL_A_LINKMODES_PEER: u16 = 4;
+const ETHTOOL_A_LINKMODES_SPEED: u16 = 5;
+const ETHTOOL_A_LINKMODES_DUPLEX: u16 = 6;
+const ETHTOOL_A_LINKMODES_LANES: u16 = 9;
+
+const ETHTOOL_A_FEATURES_HW: u16 = 2;
+const ETHTOOL_A_FEATURES_ACTIVE: u16 = 4;
+const ETHTOOL_A_FEATURES_NOCHANGE: u16 = 5;
+
+
+const ETHTOOL_A_RINGS_RX_MAX: u16 = 2;
+const ETHTOOL_A_RINGS_RX_MINI_MAX: u16 = 3;
+const ETHTOOL_A_RINGS_RX_JUMBO_MAX: u16 = 4;
+const ETHTOOL_A_RINGS_TX_MAX: u16 = 5;
+const ETHTOOL_A_RINGS_RX: u16 = 6;
+const ETHTOOL_A_RINGS_RX_MINI: u16 = 7;
+const ETHTOOL_A_RINGS_RX_JUMBO: u16 = 8;
+const ETHTOOL_A_RINGS_TX: u16 = 9;
+const ETHTOOL_A_RINGS_RX_BUF_LEN: u16 = 10;
+const ETHTOOL_A_RINGS_TCP_DATA_SPLIT: u16 = 11;
+const ETHTOOL_A_RINGS_CQE_SIZE: u16 = 12;
+const ETHTOOL_A_RINGS_TX_PUSH: u16 = 13;
+const ETHTOOL_A_RINGS_RX_PUSH: u16 = 14;
+const ETHTOOL_A_RINGS_TX_PUSH_BUF_LEN: u16 = 15;
+const ETHTOOL_A_RINGS_TX_PUSH_BUF_LEN_MAX: u16 = 16;
+const ETHTOOL_A_RINGS_HDS_THRESH: u16 = 17;
+const ETHTOOL_A_RINGS_HDS_THRESH_MAX: u16 = 18;
+
+const ETHTOOL_A_CHANNELS_RX_MAX: u16 = 2;
+const ETHTOOL_A_CHANNELS_TX_MAX: u16 = 3;
+const ETHTOOL_A_CHANNELS_OTHER_MAX: u16 = 4;
+const ETHTOOL_A_CHANNELS_COMBINED_MAX: u16 = 5;
+const ETHTOOL_A_CHANNELS_RX_COUNT: u16 = 6;
+const ETHTOOL_A_CHANNELS_TX_COUNT: u16 = 7;
+const ETHTOOL_A_CHANNELS_OTHER_COUNT: u16 = 8;
+const ETHTOOL_A_CHANNELS_COMBINED_COUNT: u16 = 9;
+
+const ETHTOOL_A_COALESCE_RX_USECS: u16 = 2;
+const ETHTOOL_A_COALESCE_RX_MAX_FRAMES: u16 = 3;
+const ETHTOOL_A_COALESCE_RX_USECS_IRQ: u16 = 4;
+const ETHTOOL_A_COALESCE_RX_MAX_FRAMES_IRQ: u16 = 5;
+const ETHTOOL_A_COALESCE_TX_USECS: u16 = 6;
+const ETHTOOL_A_COALESCE_TX_MAX_FRAMES: u16 = 7;
+const ETHTOOL_A_COALESCE_USE_ADAPTIVE_RX: u16 = 11;
+const ETHTOOL_A_COALESCE_USE_ADAPTIVE_TX: u16 = 12;
+const ETHTOOL_A_COALESCE_USE_CQE_MODE_TX: u16 = 24;
+const ETHTOOL_A_COALESCE_USE_CQE_MODE_RX: u16 = 25;
+
+const ETHTOOL_A_PAUSE_AUTONEG: u16 = 2;
+const ETHTOOL_A_PAUSE_RX: u16 = 3;
+const ETHTOOL_A_PAUSE_TX: u16 = 4;
+
+/// SPEED_UNKNOWN and DUPLEX_FULL from linux/ethtool.h
+const SPEED_UNKNOWN: u32 = u32::MAX;
+const DUPLEX_HALF: u8 = 0;
+const DUPLEX_FULL: u8 = 1;
+
+/// PORT_* connector types
+const PORTS: &[(u8, &str)] = &[
+    (0x00, "twisted pair"),
+    (0x01, "AUI"),
+    (0x02, "BNC"),
+    (0x03, "MII"),
+    (0x04, "fibre"),
+    (0x05, "direct attach copper"),
+    (0xef, "none"),
+    (0xff, "other"),
+];
+
+/// driver identification from ethtool (struct ethtool_drvinfo)
+#[repr(C)]
+struct EthtoolDrvInfo {
+    cmd: u32,
+    driver: [u8; 32],
+    version: [u8; 32],
+    fw_version: [u8; 32],
+    bus_info: [u8; 32],
+    erom_version: [u8; 32],
+    reserved2: [u8; 12],
+    n_priv_flags: u32,
+    n_stats: u32,
+    testinfo_len: u32,
+    eedump_len: u32,
+    regdump_len: u32,
+}
+
+/// legacy link settings (struct ethtool_cmd), what ETHTOOL_GSET fills in
+#[repr(C)]
+#[derive(Default)]
+struct EthtoolCmd {
+    cmd: u32,
+    supported: u32,
+    advertising: u32,
+    speed: u16,
+    duplex: u8,
+    port: u8,
+    phy_address: u8,
+    transceiver: u8,
+    autoneg: u8,
+    mdio_support: u8,
+    maxtxpkt: u32,
+    maxrxpkt: u32,
+    speed_hi: u16,
+    eth_tp_mdix: u8,
+    eth_tp_mdix_ctrl: u8,
+    lp_advertising: u32,
+    reserved: [u32; 2],
+}
+
+/// ring buffer parameters from ethtool
+#[repr(C)]
+#[derive(Default)]
+struct EthtoolRingParam {
+    cmd: u32,
+    rx_max_pending: u32,
+    rx_mini_max_pending: u32,
+    rx_jumbo_max_pending: u32,
+    tx_max_pending: u32,
+    rx_pending: u32,
+    rx_mini_pending: u32,
+    rx_jumbo_pending: u32,
+    tx_pending: u32,
+}
+
+/// channel counts from ethtool (struct ethtool_channels)
+#[repr(C)]
+#[derive(Default)]
+struct EthtoolChannels {
+    cmd: u32,
+    max_rx: u32,
+    max_tx: u32,
+    max_other: u32,
+    max_combined: u32,
+    rx_count: u32,
+    tx_count: u32,
+    other_count: u32,
+    combined_count: u32,
+}
+
+/// struct ethtool_coalesce
+#[repr(C)]
+#[derive(Default)]
+struct EthtoolCoalesce {
+    cmd: u32,
+    rx_coalesce_usecs: u32,
+    rx_max_coalesced_frames: u32,
+    rx_coalesce_usecs_irq: u32,
+    rx_max_coalesced_frames_irq: u32,
+    tx_coalesce_usecs: u32,
+    tx_max_coalesced_frames: u32,
+    tx_coalesce_usecs_irq: u32,
+    tx_max_coalesced_frames_irq: u32,
+    stats_block_coalesce_usecs: u32,
+    use_adaptive_rx_coalesce: u32,
+    use_adaptive_tx_coalesce: u32,
+    pkt_rate_low: u32,
+    rx_coalesce_usecs_low: u32,
+    rx_max_coalesced_frames_low: u32,
+    tx_coalesce_usecs_low: u32,
+    tx_max_coalesced_frames_low: u32,
+    pkt_rate_high: u32,
+    rx_coalesce_usecs_high: u32,
+    rx_max_coalesced_frames_high: u32,
+    tx_coalesce_usecs_high: u32,
+    tx_max_coalesced_frames_high: u32,
+    rate_sample_interval: u32,
+}
+
+/// struct ethtool_pauseparam
+#[repr(C)]
+#[derive(Default)]
+struct EthtoolPauseParam {
+    cmd: u32,
+    autoneg: u32,
+    rx_pause: u32,
+    tx_pause: u32,
+}
+
+/// struct ethtool_sset_info asking for a single string set
+#[repr(C)]
+#[derive(Default)]
+struct EthtoolSsetInfo {
+    cmd: u32,
+    reserved: u32,
+    sset_mask: u64,
+    data: u32,
+    _pad: u32,
+}
+
+/// fixed part of struct ethtool_rxfh, rss_config[] follows
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct EthtoolRxfh {
+    cmd: u32,
+    rss_context: u32,
+    indir_size: u32,
+    key_size: u32,
+    hfunc: u8,
+    input_xfrm: u8,
+    rsvd8: [u8; 2],
+    rsvd32: u32,
+}
+
+/// leading part of struct ethtool_rxnfc, all GRXFH copies
+#[repr(C)]
+#[derive(Default)]
+struct EthtoolRxnfc {
+    cmd: u32,
+    flow_type: u32,
+    data: u64,
+}
+
+/// what ETHTOOL_GDRVINFO reports, empty strings are dropped
+pub struct DriverInfo {
+    pub driver: String,
+    pub version: Option<String>,
+    pub firmware: Option<String>,
+}
+
+/// speed, duplex and link modes
+#[derive(Debug, Default)]
+pub struct LinkSettings {
+    /// Mb/s, None while the link is down
+    pub speed: Option<u32>,
+    /// None while the link is down
+    pub full_duplex: Option<bool>,
+    pub autoneg: bool,
+    pub port: Option<u8>,
+    pub lanes: Option<u32>,
+    /// link mode names, only from netlink
+    pub advertised: Vec<String>,
+    pub peer: Vec<String>,
+}
+
+impl LinkSettings {
+    pub fn port_name(&self) -> Option<&'static str> {
+        let port = self.port?;
+        PORTS.iter().find(|(p, _)| *p == port).map(|(_, name)| *name)
+    }
+}
+
+/// netdev features by name (ethtool -k)
+#[derive(Debug, Default)]
+pub struct Features {
+    /// features the driver lets the user toggle
+    pub hw: BTreeSet<String>,
+    pub active: BTreeSet<String>,
+    /// features the kernel never lets change, on or off, whatever `hw` says
+    pub fixed: BTreeSet<String>,
+}
+
+impl Features {
+    /// whether `ethtool -K` can toggle `name`, ethtool shows the rest as [fixed]
+    pub fn changeable(&self, name: &str) -> bool {
+        self.hw.contains(name) && !self.fixed.contains(name)
+    }
+}
+
+/// flow control (ethtool -a)
+#[derive(Debug, Default)]
+pub struct Pause {
+    pub autoneg: bool,
+    pub rx: bool,
+    pub tx: bool,
+}
+
+/// ring sizes (ethtool -g). rings a driver has no maximum for, and settings it
+/// does not support, are None
+#[derive(Debug, Clone, Default)]
+pub struct Rings {
+    pub rx_max: Option<u32>,
+    pub rx_mini_max: Option<u32>,
+    pub rx_jumbo_max: Option<u32>,
+    pub tx_max: Option<u32>,
+    pub rx: Option<u32>,
+    pub rx_mini: Option<u32>,
+    pub rx_jumbo: Option<u32>,
+    pub tx: Option<u32>,
+    pub rx_buf_len: Option<u32>,
+    /// ETHTOOL_TCP_DATA_SPLIT_*: 0 unknown, 1 disabled, 2 enabled
+    pub tcp_data_split: Option<u8>,
+    pub cqe_size: Option<u32>,
+    pub tx_push: Option<bool>,
+    pub rx_push: Option<bool>,
+    pub tx_push_buf_len: Option<u32>,
+    pub tx_push_buf_len_max: Option<u32>,
+    pub hds_thresh: Option<u32>,
+    pub hds_thresh_max: Option<u32>,
+}
+
+/// channel counts (ethtool -l), 0 for channel types the driver lacks
+#[derive(Debug, Default)]
+pub struct Channels {
+    pub max_rx: u32,
+    pub max_tx: u32,
+    pub max_other: u32,
+    pub max_combined: u32,
+    pub rx_count: u32,
+    pub tx_count: u32,
+    pub other_count: u32,
+    pub combined_count: u32,
+}
+
+/// interrupt coalescing (ethtool -c). over netlink the kernel leaves out the
+/// parameters a driver does not support, the ioctl reports all of them
+#[derive(Debug, Default)]
+pub struct Coalesce {
+    pub rx_usecs: Option<u32>,
+    pub rx_max_frames: Option<u32>,
+    pub rx_usecs_irq: Option<u32>,
+    pub rx_max_frames_irq: Option<u32>,
+    pub tx_usecs: Option<u32>,
+    pub tx_max_frames: Option<u32>,
+    pub adaptive_rx: Option<bool>,
+    pub adaptive_tx: Option<bool>,
+    pub cqe_mode_rx: Option<bool>,
+    pub cqe_mode_tx: Option<bool>,
+}
+
+/// RSS indirection table, hash key and function
+pub struct Rss {
+    pub indir: Vec<u32>,
+    pub key: Vec<u8>,
+    pub hfunc: u8,
+}
+
+/// id of the "ethtool" genetlink family, looked up once
+fn family() -> Result<u16> {
+    static FAMILY: OnceLock<u16> = OnceLock::new();
+    if let Some(id) = FAMILY.get() {
+        return Ok(*id);
+    }
+    let id = netlink::genl_family_id("ethtool")?;
+    Ok(*FAMILY.get_or_init(|| id))
+}
+
+/// one ethtool netlink get request for `interface`, the attributes of every reply
+fn get(interface: &str, cmd: u8) -> Result<Vec<Vec<u8>>> {
+    let mut name = interface.as_bytes().to_vec();
+    name.push(0);
+    let mut header = Vec::new();
+    netlink::put_attr(&mut header, ETHTOOL_A_HEADER_DEV_NAME, &name);
+    let mut attrs = Vec::new();
+    netlink::put_attr(&mut attrs, ETHTOOL_A_HEADER | netlink::NLA_F_NESTED, &header);
+
+    netlink::genl_request(family()?, cmd, ETHTOOL_GENL_VERSION, 0, &attrs)
+}
+
+/// the netlink query, or the ioctl on kernels without ethtool netlink (before 5.6)
+fn with_fallback<T>(
+    interface: &str,
+    what: &str,
+    netlink: impl FnOnce() -> Result<T>,
+    ioctl: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    netlink().or_else(|e| {
+        log::debug!("{}: ethtool netlink {} failed, trying the ioctl: {:#}", interface, what, e);
+        ioctl()
+    })
+}
+
+fn u32_attr(value: &[u8]) -> Option<u32> {
+    Some(ne_u32(value, 0))
+}
+
+fn bool_attr(value: &[u8]) -> Option<bool> {
+    value.first().map(|v| *v != 0)
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+/// (name, value) of every bit listed in a verbose ETHTOOL_A_BITSET. without a
+/// mask only the set bits are listed
+fn bitset(nest: &[u8]) -> Vec<(String, bool)> {
+    let attrs = attributes(nest);
+    let nomask = attrs.iter().any(|(kind, _)| *kind == ETHTOOL_A_BITSET_NOMASK);
+
+    attrs
+        .iter()
+        .filter(|(kind, _)| *kind == ETHTOOL_A_BITSET_BITS)
+        .flat_map(|(_, bits)| attributes(bits))
+        .filter(|(kind, _)| *kind == ETHTOOL_A_BITSET_BITS_BIT)
+        .filter_map(|(_, bit)| {
+            let bit = attributes(bit);
+            let name = bit
+                .iter()
+                .find(|(kind, _)| *kind == ETHTOOL_A_BITSET_BIT_NAME)
+                .map(|(_, value)| c_string(value))?;
+            let set = nomask || bit.iter().any(|(kind, _)| *kind == ETHTOOL_A_BITSET_BIT_VALUE);
+            Some((name, set))
+        })
+        .collect()
+}
+
+fn set_bits(nest: &[u8]) -> BTreeSet<String> {
+    bitset(nest).into_iter().filter(|(_, set)| *set).map(|(name, _)| name).collect()
+}
+
+/// driver name, version and firmware via ETHTOOL_GDRVINFO, ethtool netlink has
+/// no equivalent
+pub fn driver_info(interface: &str) -> Result<DriverInfo> {
+    let mut drvinfo: EthtoolDrvInfo = unsafe { mem::zeroed() };
+    drvinfo.cmd = ETHTOOL_GDRVINFO;
+
+    ethtool_ioctl(interface, &mut drvinfo)?;
+
+    let text = |bytes: &[u8]| {
+        let value = c_string(bytes);
+        (!value.is_empty() && value != "N/A").then_some(value)
+    };
+
+    Ok(DriverInfo {
+        driver: text(&drvinfo.driver).ok_or_else(|| anyhow!("ethtool reported no driver"))?,
+        version: text(&drvinfo.version),
+        firmware: text(&drvinfo.fw_version),
+    })
+}
+
+/// speed, duplex, port and link modes
+pub fn link(interface: &str) -> Result<LinkSettings> {
+    with_fallback(interface, "link modes", || link_netlink(interface), || link_ioctl(interface))
+}
+
+fn link_netlink(interface: &str) -> Result<LinkSettings> {
+    let mut link = LinkSettings::default();
+
+    for reply in get(interface, ETHTOOL_MSG_LINKINFO_GET)? {
+        for (kind, value) in attributes(&reply) {
+            if kind == ETHTOOL_A_LINKINFO_PORT {
+                link.port = value.first().copied();
+            }
+        }
+    }
+
+    for reply in get(interface, ETHTOOL_MSG_LINKMODES_GET)? {
+        for (kind, value) in attributes(&reply) {
+            match kind {
+                ETHTOOL_A_LINKMODES_AUTONEG => link.autoneg = bool_attr(value).unwrap_or(false),
+                ETHTOOL_A_LINKMODES_SPEED => link.speed = u32_attr(value).filter(|s| *s != SPEED_UNKNOWN),
+                ETHTOOL_A_LINKMODES_DUPLEX => {
+                    link.full_duplex = match value.first() {
+                        Some(&DUPLEX_FULL) => Some(true),
+                        Some(&DUPLEX_HALF) => Some(false),
+                        _ => None,
+                    }
+                }
+                ETHTOOL_A_LINKMODES_LANES => link.lanes = u32_attr(value),
+                // supported modes are the mask, advertised the value
+                ETHTOOL_A_LINKMODES_OURS => {
+                    link.advertised = bitset(value)
+                        .into_iter()
+                        .filter(|(_, advertised)| *advertised)
+                        .map(|(name, _)| name)
+                        .collect()
+                }
+                ETHTOOL_A_LINKMODES_PEER => link.peer = set_bits(value).into_iter().collect(),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(link)
+}
+
+/// ETHTOOL_GSET, the link mode bits are not decoded
+fn link_ioctl(interface: &str) -> Result<LinkSettings> {
+    let mut cmd = EthtoolCmd {
+        cmd: ETHTOOL_GSET,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut cmd)?;
+
+    let speed = ((cmd.speed_hi as u32) << 16) | cmd.speed as u32;
+    Ok(LinkSettings {
+        speed: (speed != 0 && speed != SPEED_UNKNOWN).then_some(speed),
+        full_duplex: match cmd.duplex {
+            DUPLEX_FULL => Some(true),
+            DUPLEX_HALF => Some(false),
+            _ => None,
+        },
+        autoneg: cmd.autoneg != 0,
+        port: Some(cmd.port),
+        ..Default::default()
+    })
+}
+
+/// netdev feature bits by name
+pub fn features(interface: &str) -> Result<Features> {
+    with_fallback(interface, "features", || features_netlink(interface), || features_ioctl(interface))
+}
+
+fn features_netlink(interface: &str) -> Result<Features> {
+    let mut features = Features::default();
+
+    for reply in get(interface, ETHTOOL_MSG_FEATURES_GET)? {
+        for (kind, value) in attributes(&reply) {
+            match kind {
+                ETHTOOL_A_FEATURES_HW => features.hw = set_bits(value),
+                ETHTOOL_A_FEATURES_ACTIVE => features.active = set_bits(value),
+                ETHTOOL_A_FEATURES_NOCHANGE => features.fixed = set_bits(value),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(features)
+}
+
+/// ETHTOOL_GFEATURES with names from the ETH_SS_FEATURES string set
+fn features_ioctl(interface: &str) -> Result<Features> {
+    let names = string_set(interface, ETH_SS_FEATURES)?;
+    let blocks = names.len().div_ceil(32);
+
+    // struct ethtool_gfeatures { cmd, size, features[size] }, each block is
+    // { available, requested, active, never_changed }
+    let mut buf = vec![0u32; 2 + blocks * 4];
+    buf[0] = ETHTOOL_GFEATURES;
+    buf[1] = blocks as u32;
+    ethtool_ioctl_raw(interface, buf.as_mut_ptr().cast())?;
+
+    let mut features = Features::default();
+    for (bit, name) in names.into_iter().enumerate() {
+        let block = &buf[2 + (bit / 32) * 4..2 + (bit / 32) * 4 + 4];
+        let mask = 1 << (bit % 32);
+        if block[0] & mask != 0 {
+            features.hw.insert(name.clone());
+        }
+        if block[2] & mask != 0 {
+            features.active.insert(name.clone());
+        }
+        if block[3] & mask != 0 {
+            features.fixed.insert(name);
+        }
+    }
+
+    Ok(features)
+}
+
+/// flow control settings
+pub fn pause(interface: &str) -> Result<Pause> {
+    with_fallback(interface, "pause", || pause_netlink(interface), || pause_ioctl(interface))
+}
+
+fn pause_netlink(interface: &str) -> Result<Pause> {
+    let mut pause = Pause::default();
+
+    for reply in get(interface, ETHTOOL_MSG_PAUSE_GET)? {
+        for (kind, value) in attributes(&reply) {
+            let set = bool_attr(value).unwrap_or(false);
+            match kind {
+                ETHTOOL_A_PAUSE_AUTONEG => pause.autoneg = set,
+                ETHTOOL_A_PAUSE_RX => pause.rx = set,
+                ETHTOOL_A_PAUSE_TX => pause.tx = set,
+                _ => {}
+            }
+        }
+    }
+
+    Ok(pause)
+}
+
+fn pause_ioctl(interface: &str) -> Result<Pause> {
+    let mut param = EthtoolPauseParam {
+        cmd: ETHTOOL_GPAUSEPARAM,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut param)?;
+
+    Ok(Pause {
+        autoneg: param.autoneg != 0,
+        rx: param.rx_pause != 0,
+        tx: param.tx_pause != 0,
+    })
+}
+
+/// current and maximum ring sizes, with rx-buf-len, tcp-data-split and the
+/// other netlink-only ring settings on kernels that have them
+pub fn rings(interface: &str) -> Result<Rings> {
+    with_fallback(interface, "rings", || rings_netlink(interface), || rings_ioctl(interface))
+}
+
+fn rings_netlink(interface: &str) -> Result<Rings> {
+    let mut rings = Rings::default();
+
+    for reply in get(interface, ETHTOOL_MSG_RINGS_GET)? {
+        for (kind, value) in attributes(&reply) {
+            match kind {
+                ETHTOOL_A_RINGS_RX_MAX => rings.rx_max = u32_attr(value),
+                ETHTOOL_A_RINGS_RX_MINI_MAX => rings.rx_mini_max = u32_attr(value),
+                ETHTOOL_A_RINGS_RX_JUMBO_MAX => rings.rx_jumbo_max = u32_attr(value),
+                ETHTOOL_A_RINGS_TX_MAX => rings.tx_max = u32_attr(value),
+                ETHTOOL_A_RINGS_RX => rings.rx = u32_attr(value),
+                ETHTOOL_A_RINGS_RX_MINI => rings.rx_mini = u32_attr(value),
+                ETHTOOL_A_RINGS_RX_JUMBO => rings.rx_jumbo = u32_attr(value),
+                ETHTOOL_A_RINGS_TX => rings.tx = u32_attr(value),
+                ETHTOOL_A_RINGS_RX_BUF_LEN => rings.rx_buf_len = u32_attr(value),
+                ETHTOOL_A_RINGS_TCP_DATA_SPLIT => rings.tcp_data_split = value.first().copied(),
+                ETHTOOL_A_RINGS_CQE_SIZE => rings.cqe_size = u32_attr(value),
+                ETHTOOL_A_RINGS_TX_PUSH => rings.tx_push = bool_attr(value),
+                ETHTOOL_A_RINGS_RX_PUSH => rings.rx_push = bool_attr(value),
+                ETHTOOL_A_RINGS_TX_PUSH_BUF_LEN => rings.tx_push_buf_len = u32_attr(value),
+                ETHTOOL_A_RINGS_TX_PUSH_BUF_LEN_MAX => rings.tx_push_buf_len_max = u32_attr(value),
+                ETHTOOL_A_RINGS_HDS_THRESH => rings.hds_thresh = u32_attr(value),
+                ETHTOOL_A_RINGS_HDS_THRESH_MAX => rings.hds_thresh_max = u32_attr(value),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(rings)
+}
+
+/// ring buffer parameters using ethtool ioctl (alessandros device check).
+/// like the netlink reply, rings without a maximum are left out
+fn rings_ioctl(interface: &str) -> Result<Rings> {
+    let mut ring_param = EthtoolRingParam {
+        cmd: ETHTOOL_GRINGPARAM,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut ring_param)?;
+
+    let ring = |max: u32, pending: u32| if max == 0 { (None, None) } else { (Some(max), Some(pending)) };
+    let (rx_max, rx) = ring(ring_param.rx_max_pending, ring_param.rx_pending);
+    let (rx_mini_max, rx_mini) = ring(ring_param.rx_mini_max_pending, ring_param.rx_mini_pending);
+    let (rx_jumbo_max, rx_jumbo) = ring(ring_param.rx_jumbo_max_pending, ring_param.rx_jumbo_pending);
+    let (tx_max, tx) = ring(ring_param.tx_max_pending, ring_param.tx_pending);
+
+    Ok(Rings {
+        rx_max,
+        rx_mini_max,
+        rx_jumbo_max,
+        tx_max,
+        rx,
+        rx_mini,
+        rx_jumbo,
+        tx,
+        ..Default::default()
+    })
+}
+
+/// channel counts, current and maximum
+pub fn channels(interface: &str) -> Result<Channels> {
+    with_fallback(interface, "channels", || channels_netlink(interface), || channels_ioctl(interface))
+}
+
+fn channels_netlink(interface: &str) -> Result<Channels> {
+    let mut channels = Channels::default();
+
+    for reply in get(interface, ETHTOOL_MSG_CHANNELS_GET)? {
+        for (kind, value) in attributes(&reply) {
+            let value = ne_u32(value, 0);
+            match kind {
+                ETHTOOL_A_CHANNELS_RX_MAX => channels.max_rx = value,
+                ETHTOOL_A_CHANNELS_TX_MAX => channels.max_tx = value,
+                ETHTOOL_A_CHANNELS_OTHER_MAX => channels.max_other = value,
+                ETHTOOL_A_CHANNELS_COMBINED_MAX => channels.max_combined = value,
+                ETHTOOL_A_CHANNELS_RX_COUNT => channels.rx_count = value,
+                ETHTOOL_A_CHANNELS_TX_COUNT => channels.tx_count = value,
+                ETHTOOL_A_CHANNELS_OTHER_COUNT => channels.other_count = value,
+                ETHTOOL_A_CHANNELS_COMBINED_COUNT => channels.combined_count = value,
+                _ => {}
+            }
+        }
+    }
+
+    Ok(channels)
+}
+
+fn channels_ioctl(interface: &str) -> Result<Channels> {
+    let mut channels = EthtoolChannels {
+        cmd: ETHTOOL_GCHANNELS,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut channels)?;
+
+    Ok(Channels {
+        max_rx: channels.max_rx,
+        max_tx: channels.max_tx,
+        max_other: channels.max_other,
+        max_combined: channels.max_combined,
+        rx_count: channels.rx_count,
+        tx_count: channels.tx_count,
+        other_count: channels.other_count,
+        combined_count: channels.combined_count,
+    })
+}
+
+/// interrupt coalescing parameters
+pub fn coalesce(interface: &str) -> Result<Coalesce> {
+    with_fallback(interface, "coalesce", || coalesce_netlink(interface), || coalesce_ioctl(interface))
+}
+
+fn coalesce_netlink(interface: &str) -> Result<Coalesce> {
+    let mut coalesce = Coalesce::default();
+
+    for reply in get(interface, ETHTOOL_MSG_COALESCE_GET)? {
+        for (kind, value) in attributes(&reply) {
+            match kind {
+                ETHTOOL_A_COALESCE_RX_USECS => coalesce.rx_usecs = u32_attr(value),
+                ETHTOOL_A_COALESCE_RX_MAX_FRAMES => coalesce.rx_max_frames = u32_attr(value),
+                ETHTOOL_A_COALESCE_RX_USECS_IRQ => coalesce.rx_usecs_irq = u32_attr(value),
+                ETHTOOL_A_COALESCE_RX_MAX_FRAMES_IRQ => coalesce.rx_max_frames_irq = u32_attr(value),
+                ETHTOOL_A_COALESCE_TX_USECS => coalesce.tx_usecs = u32_attr(value),
+                ETHTOOL_A_COALESCE_TX_MAX_FRAMES => coalesce.tx_max_frames = u32_attr(value),
+                ETHTOOL_A_COALESCE_USE_ADAPTIVE_RX => coalesce.adaptive_rx = bool_attr(value),
+                ETHTOOL_A_COALESCE_USE_ADAPTIVE_TX => coalesce.adaptive_tx = bool_attr(value),
+                ETHTOOL_A_COALESCE_USE_CQE_MODE_RX => coalesce.cqe_mode_rx = bool_attr(value),
+                ETHTOOL_A_COALESCE_USE_CQE_MODE_TX => coalesce.cqe_mode_tx = bool_attr(value),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(coalesce)
+}
+
+fn coalesce_ioctl(interface: &str) -> Result<Coalesce> {
+    let mut param = EthtoolCoalesce {
+        cmd: ETHTOOL_GCOALESCE,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut param)?;
+
+    Ok(Coalesce {
+        rx_usecs: Some(param.rx_coalesce_usecs),
+        rx_max_frames: Some(param.rx_max_coalesced_frames),
+        rx_usecs_irq: Some(param.rx_coalesce_usecs_irq),
+        rx_max_frames_irq: Some(param.rx_max_coalesced_frames_irq),
+        tx_usecs: Some(param.tx_coalesce_usecs),
+        tx_max_frames: Some(param.tx_max_coalesced_frames),
+        adaptive_rx: Some(param.use_adaptive_rx_coalesce != 0),
+        adaptive_tx: Some(param.use_adaptive_tx_coalesce != 0),
+        // CQE mode has no ioctl
+        cqe_mode_rx: None,
+        cqe_mode_tx: None,
+    })
+}
+
+/// ethtool -S names and values via ETHTOOL_GSTRINGS and ETHTOOL_GSTATS. ethtool
+/// netlink only carries the standard IEEE/RMON groups, driver counters (where
+/// the XDP ones live) are ioctl only
+pub fn stats(interface: &str) -> Result<Vec<(String, u64)>> {
+    let names = string_set(interface, ETH_SS_STATS)?;
+    let count = names.len();
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    // struct ethtool_stats { cmd, n_stats, data[] } with u64 data
+    let mut stats = vec![0u64; 1 + count];
+    let mut header = [0u8; 8];
+    header[..4].copy_from_slice(&ETHTOOL_GSTATS.to_ne_bytes());
+    header[4..].copy_from_slice(&(count as u32).to_ne_bytes());
+    stats[0] = u64::from_ne_bytes(header);
+    ethtool_ioctl_raw(interface, stats.as_mut_ptr().cast())?;
+
+    Ok(names.into_iter().zip(stats[1..].iter().copied()).collect())
+}
+
+/// RSS indirection table and key via ETHTOOL_GRSSH, sizes are queried first
+pub fn rss(interface: &str) -> Result<Rss> {
+    let mut header = EthtoolRxfh {
+        cmd: ETHTOOL_GRSSH,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut header)?;
+
+    let header_len = mem::size_of::<EthtoolRxfh>();
+    let indir_len = header.indir_size as usize * 4;
+    let mut buf = vec![0u32; (header_len + indir_len + header.key_size as usize).div_ceil(4)];
+    let request = EthtoolRxfh {
+        cmd: ETHTOOL_GRSSH,
+        indir_size: header.indir_size,
+        key_size: header.key_size,
+        ..Default::default()
+    };
+    unsafe { ptr::write(buf.as_mut_ptr().cast::<EthtoolRxfh>(), request) };
+    ethtool_ioctl_raw(interface, buf.as_mut_ptr().cast())?;
+
+    let bytes: Vec<u8> = buf.iter().flat_map(|w| w.to_ne_bytes()).collect();
+    let hfunc = bytes[16];
+    let indir = bytes[header_len..header_len + indir_len]
+        .chunks_exact(4)
+        .map(|c| u32::from_ne_bytes(c.try_into().unwrap()))
+        .collect();
+    let key = bytes[header_len + indir_len..header_len + indir_len + header.key_size as usize].to_vec();
+
+    Ok(Rss { indir, key, hfunc })
+}
+
+/// RXH_* fields hashed for a flow type via ETHTOOL_GRXFH
+pub fn rx_flow_hash(interface: &str, flow_type: u32) -> Result<u64> {
+    let mut rxnfc = EthtoolRxnfc {
+        cmd: ETHTOOL_GRXFH,
+        flow_type,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut rxnfc)?;
+    Ok(rxnfc.data)
+}
+
+/// names of string set `set` via ETHTOOL_GSSET_INFO and ETHTOOL_GSTRINGS
+fn string_set(interface: &str, set: u32) -> Result<Vec<String>> {
+    let mut info = EthtoolSsetInfo {
+        cmd: ETHTOOL_GSSET_INFO,
+        sset_mask: 1 << set,
+        ..Default::default()
+    };
+    ethtool_ioctl(interface, &mut info)?;
+    // the kernel clears the bit of a set the driver does not have
+    let count = if info.sset_mask & (1 << set) != 0 { info.data as usize } else { 0 };
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    // struct ethtool_gstrings { cmd, string_set, len, data[] }
+    let mut strings = vec![0u32; 3 + count * ETH_GSTRING_LEN / 4];
+    strings[..3].copy_from_slice(&[ETHTOOL_GSTRINGS, set, count as u32]);
+    ethtool_ioctl_raw(interface, strings.as_mut_ptr().cast())?;
+
+    let names: Vec<u8> = strings[3..].iter().flat_map(|w| w.to_ne_bytes()).collect();
+    Ok(names.chunks_exact(ETH_GSTRING_LEN).map(c_string).collect())
+}
+
+/// SIOCETHTOOL with `data` as the command buffer, `data` must start with the cmd word
+fn ethtool_ioctl<T>(interface: &str, data: &mut T) -> Result<()> {
+    ethtool_ioctl_raw(interface, (data as *mut T).cast())
+}
+
+/// SIOCETHTOOL for variable length commands, `data` must stay valid for the call
+fn ethtool_ioctl_raw(interface: &str, data: *mut libc::c_void) -> Result<()> {
+    // create socket for ioctl
+    let fd = unsafe { socket(AF_INET, SOCK_DGRAM, 0) };
+    if fd < 0 {
+        return Err(anyhow!("Failed to create socket"));
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut ifr: ifreq = unsafe { mem::zeroed() };
+    let if_name_bytes = interface.as_bytes();
+    let len = if_name_bytes.len().min(IF_NAMESIZE - 1);
+    unsafe {
+        ptr::copy_nonoverlapping(
+            if_name_bytes.as_ptr() as *const i8,
+            ifr.ifr_name.as_mut_ptr(),
+            len,
+        );
+    }
+    ifr.ifr_name[IF_NAMESIZE - 1] = 0;
+    ifr.ifr_ifru.ifru_data = data.cast();
+
+    let res = unsafe { ioctl(fd.as_raw_fd(), SIOCETHTOOL, &ifr) };
+    if res < 0 {
+        return Err(anyhow!(
+            "ethtool ioctl failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use anyhow::{bail, Context, Result};
+use aya::programs::{loaded_programs, ProgramInfo, ProgramType};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::elf;
+use crate::netlink::{self, XdpAttachment, XdpMode};
+use crate::output::{CheckResult, CheckStatus};
+
+/// checked when neither --program nor a config file is given
+const DEFAULT_PROGRAM: &str = "agave_xdp";
+
+/// config file layout, one [[program]] table per expected program
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProgramsFile {
+    #[serde(default, rename = "program")]
+    programs: Vec<ProgramRule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProgramRule {
+    name: String,
+    #[serde(default = "default_required")]
+    required: bool,
+    #[serde(default)]
+    interfaces: Vec<String>,
+    /// "native", "generic" or "offload"
+    mode: Option<String>,
+    /// expected tag, 16 hex digits
+    tag: Option<String>,
+    /// object file to compute the expected tag from
+    elf: Option<PathBuf>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// the build a program must be, from a pinned tag or an ELF object
+pub struct ExpectedTag {
+    pub tag: u64,
+    /// where the tag came from, for messages
+    pub source: String,
+}
+
+impl ExpectedTag {
+    fn resolve(program: &str, tag: Option<&str>, elf_path: Option<&Path>) -> Result<Option<Self>> {
+        if let Some(tag) = tag {
+            return Ok(Some(ExpectedTag {
+                tag: elf::parse_tag(tag)?,
+                source: "expected tag".to_string(),
+            }));
+        }
+
+        match elf_path {
+            Some(path) => Ok(Some(ExpectedTag {
+                tag: elf::program_tag(path, program)?,
+                source: path.display().to_string(),
+            })),
+            None => Ok(None),
+        }
+    }
+}
+
+/// one program that should be loaded, and where and how it should be attached
+pub struct ExpectedProgram {
+    pub name: String,
+    /// missing or misattached required programs fail, optional ones only warn
+    pub required: bool,
+    pub interfaces: Vec<String>,
+    pub mode: Option<XdpMode>,
+    pub tag: Option<ExpectedTag>,
+}
+
+impl ExpectedProgram {
+    fn failure(&self) -> CheckStatus {
+        if self.required {
+            CheckStatus::Fail
+        } else {
+            CheckStatus::Warning
+        }
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<XdpMode> {
+    match mode {
+        "native" | "driver" | "drv" => Ok(XdpMode::Native),
+        "generic" | "skb" => Ok(XdpMode::Generic),
+        "offload" | "hw" => Ok(XdpMode::Offload),
+        other => bail!("Unknown attach mode '{}', expected native, generic or offload", other),
+    }
+}
+
+/// expected programs from `--program` names and the config file. `expect_tag`
+/// and `expect_elf` apply to the command line programs, or to agave_xdp when
+/// there are none. with no names and no config, agave_xdp is checked as an
+/// optional program.
+pub fn expected_programs(
+    names: &[String],
+    config: Option<&Path>,
+    expect_tag: Option<&str>,
+    expect_elf: Option<&Path>,
+) -> Result<Vec<ExpectedProgram>> {
+    let mut expected = Vec::new();
+
+    // the rules file has its own tag and elf keys, a global one would match nothing
+    if names.is_empty() && config.is_some() && (expect_tag.is_some() || expect_elf.is_some()) {
+        bail!("--expect-tag and --expect-elf apply to --program names, set tag or elf in the rules file instead");
+    }
+
+    let cli_names: Vec<&str> = if names.is_empty() && config.is_none() {
+        vec![DEFAULT_PROGRAM]
+    } else {
+        names.iter().map(String::as_str).collect()
+    };
+    let defaulted = names.is_empty() && config.is_none();
+
+    for name in cli_names {
+        expected.push(ExpectedProgram {
+            name: name.to_string(),
+            // an explicit --program or a pinned build means the user wants it there
+            required: !defaulted || expect_tag.is_some() || expect_elf.is_some(),
+            interfaces: Vec::new(),
+            mode: None,
+            tag: ExpectedTag::resolve(name, expect_tag, expect_elf)?,
+        });
+    }
+
+    if let Some(path) = config {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read program list {}", path.display()))?;
+        let file: ProgramsFile = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse program list {}", path.display()))?;
+
+        for rule in file.programs {
+            let mode = rule
+                .mode
+                .as_deref()
+                .map(parse_mode)
+                .transpose()
+                .with_context(|| format!("{}: program {}", path.display(), rule.name))?;
+            // relative ELF paths are relative to the config file
+            let elf_path = rule.elf.map(|elf| match path.parent() {
+                Some(dir) if elf.is_relative() => dir.join(elf),
+                _ => elf,
+            });
+            let tag = ExpectedTag::resolve(&rule.name, rule.tag.as_deref(), elf_path.as_deref())
+                .with_context(|| format!("{}: program {}", path.display(), rule.name))?;
+
+            expected.push(ExpectedProgram {
+                name: rule.name,
+                required: rule.required,
+                interfaces: rule.interfaces,
+                mode,
+                tag,
+            });
+        }
+    }
+
+    Ok(expected)
+}
+
+/// one result per rule of every expected program. with `interface` only the
+/// rules for that interface are evaluated.
+pub fn check_expected_programs(expected: &[ExpectedProgram], interface: Option<&str>) -> Vec<CheckResult> {
+    let xdp_programs: Vec<ProgramInfo> = loaded_programs()
+        .filter_map(|r| r.ok())
+        .filter(|p| matches!(p.program_type(), Ok(ProgramType::Xdp)))
+        .collect();
+
+    let attachments = match netlink::xdp_attachments() {
+        Ok(attachments) => attachments,
+        Err(e) => {
+            log::debug!("RTM_GETLINK dump failed: {:#}", e);
+            Vec::new()
+        }
+    };
+
+    expected
+        .iter()
+        .flat_map(|program| check_program(program, &xdp_programs, &attachments, interface))
+        .collect()
+}
+
+fn check_program(
+    expected: &ExpectedProgram,
+    xdp_programs: &[ProgramInfo],
+    attachments: &[XdpAttachment],
+    interface: Option<&str>,
+) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    let name = &expected.name;
+
+    let loaded: Vec<&ProgramInfo> = xdp_programs
+        .iter()
+        .filter(|p| p.name_as_str() == Some(name.as_str()))
+        .collect();
+
+    if loaded.is_empty() {
+        let others: Vec<&str> = xdp_programs.iter().filter_map(|p| p.name_as_str()).collect();
+        results.push(CheckResult {
+            name: format!("Program {}", name),
+            // an optional program is only worth a warning when something else runs instead
+            status: if expected.required || !xdp_programs.is_empty() {
+                expected.failure()
+            } else {
+                CheckStatus::Info
+            },
+            message: format!(
+                "{} is not loaded{}",
+                name,
+                if expected.required { "" } else { " (optional)" }
+            ),
+            details: Some(if others.is_empty() {
+                "No XDP programs currently loaded".to_string()
+            } else {
+                format!("Loaded XDP programs: {}", others.join(", "))
+            }),
+        });
+        return results;
+    }
+
+    let ids: Vec<u32> = loaded.iter().map(|p| p.id()).collect();
+    results.push(CheckResult {
+        name: format!("Program {}", name),
+        status: CheckStatus::Pass,
+        message: format!(
+            "{} is loaded (ID: {})",
+            name,
+            ids.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+        ),
+        details: Some(
+            loaded
+                .iter()
+                .map(|p| format!("Program ID: {}\nProgram Tag: {:016x}\nType: XDP", p.id(), p.tag()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    });
+
+    if let Some(tag) = &expected.tag {
+        for prog in &loaded {
+            results.push(check_tag(expected, tag, prog));
+        }
+    }
+
+    // (interface, mode) of every attachment of one of our program IDs
+    let attached: Vec<(&str, XdpMode)> = attachments
+        .iter()
+        .flat_map(|a| {
+            a.programs()
+                .into_iter()
+                .filter(|(_, id)| ids.contains(id))
+                .map(move |(mode, _)| (a.name.as_str(), mode))
+        })
+        .filter(|(iface, _)| interface.is_none_or(|filter| filter == *iface))
+        .collect();
+
+    let wanted: Vec<&str> = expected
+        .interfaces
+        .iter()
+        .map(String::as_str)
+        .filter(|iface| interface.is_none_or(|filter| filter == *iface))
+        .collect();
+
+    for iface in &wanted {
+        let modes: Vec<XdpMode> = attached
+            .iter()
+            .filter(|(name, _)| name == iface)
+            .map(|(_, mode)| *mode)
+            .collect();
+        results.push(check_attachment(expected, iface, &modes));
+    }
+
+    // a mode without interfaces applies wherever the program is attached
+    if expected.interfaces.is_empty() {
+        if let Some(mode) = expected.mode {
+            if attached.is_empty() {
+                results.push(CheckResult {
+                    name: format!("Program {}: Attach Mode", name),
+                    status: expected.failure(),
+                    message: format!("{} is not attached to any interface", name),
+                    details: Some(format!("Expected {} mode", mode.as_str())),
+                });
+            }
+            // one result per interface, not per attachment mode
+            let mut ifaces: Vec<&str> = attached.iter().map(|(iface, _)| *iface).collect();
+            ifaces.dedup();
+            for iface in ifaces {
+                let modes: Vec<XdpMode> = attached
+                    .iter()
+                    .filter(|(name, _)| *name == iface)
+                    .map(|(_, mode)| *mode)
+                    .collect();
+                results.push(check_attachment(expected, iface, &modes));
+            }
+        }
+    }
+
+    results
+}
+
+fn check_tag(expected: &ExpectedProgram, tag: &ExpectedTag, prog: &ProgramInfo) -> CheckResult {
+    let name = format!("Program {}: Tag", expected.name);
+
+    if prog.tag() == tag.tag {
+        CheckResult {
+            name,
+            status: CheckStatus::Pass,
+            message: format!("{} (ID: {}) matches the expected build", expected.name, prog.id()),
+            details: Some(format!("Tag {:016x} from {}", tag.tag, tag.source)),
+        }
+    } else {
+        CheckResult {
+            name,
+            status: expected.failure(),
+            message: format!(
+                "{} tag {:016x} does not match expected {:016x} (ID: {})",
+                expected.name,
+                prog.tag(),
+                tag.tag,
+                prog.id()
+            ),
+            details: Some(format!(
+                "Expected tag from {}. The loaded program is a different build, likely stale after an upgrade.",
+                tag.source
+            )),
+        }
+    }
+}
+
+fn check_attachment(expected: &ExpectedProgram, iface: &str, modes: &[XdpMode]) -> CheckResult {
+    let name = format!("Program {}: {}", expected.name, iface);
+    let wanted = expected.mode.map(|m| format!(" in {} mode", m.as_str())).unwrap_or_default();
+
+    if modes.is_empty() {
+        return CheckResult {
+            name,
+            status: expected.failure(),
+            message: format!("{} is not attached to {}", expected.name, iface),
+            details: Some(format!("Expected on {}{}", iface, wanted)),
+        };
+    }
+
+    let actual: Vec<&str> = modes.iter().map(|m| m.as_str()).collect();
+    match expected.mode {
+        Some(mode) if !modes.contains(&mode) => CheckResult {
+            name,
+            status: expected.failure(),
+            message: format!(
+                "{} is attached to {} in {} mode, expected {}",
+                expected.name,
+                iface,
+                actual.join(", "),
+                mode.as_str()
+            ),
+            details: Some(mode_mismatch(mode, modes)),
+        },
+        _ => CheckResult {
+            name,
+            status: CheckStatus::Pass,
+            message: format!("{} is attached to {} in {} mode", expected.name, iface, actual.join(", ")),
+            details: None,
+        },
+    }
+}
+
+/// why running in `modes` instead of the expected `mode` matters
+fn mode_mismatch(mode: XdpMode, modes: &[XdpMode]) -> String {
+    if modes.contains(&XdpMode::Generic) {
+        return "Generic (skb) mode runs after the driver and costs most of XDP's benefit".to_string();
+    }
+
+    match mode {
+        XdpMode::Offload => "The rule expects the program offloaded to the NIC, it runs on the host CPU instead".to_string(),
+        _ => format!("The rule expects {} mode, reattach the program in that mode or update the rule", mode.as_str()),
+    }
+}
@@ -1,8 +1,15 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
+use std::str::FromStr;
 use nix::sys::utsname;
 
+use crate::bpf;
 use crate::output::{CheckResult, CheckStatus};
 
 /// min means it will not work
@@ -17,6 +24,8 @@ pub fn check_kernel_compatibility() -> Result<Vec<CheckResult>> {
 
     results.extend(check_kernel_config()?);
 
+    results.extend(bpf::probe_features());
+
     results.push(check_btf_support());
 
     results.extend(check_kernel_modules()?);
@@ -29,28 +38,202 @@ pub fn quick_kernel_check() -> Result<Vec<CheckResult>> {
     Ok(vec![check_kernel_version()?])
 }
 
-fn check_kernel_version() -> Result<CheckResult> {
-    let uname = utsname::uname()?;
-    let release = uname.release().to_str().unwrap_or("unknown");
-
-    // parse kernel version (e.g., "x.xx.x-xx-generic" -> (5, 15, 0))
-    let parts: Vec<&str> = release.split(&['.', '-'][..]).collect();
-    if parts.len() < 2 {
-        return Ok(CheckResult {
-            name: "Kernel Version".to_string(),
-            status: CheckStatus::Error,
-            message: format!("Unable to parse kernel version: {}", release),
-            details: None,
-        });
+/// (major, minor)
+type MajorMinor = (u32, u32);
+
+/// approximate BPF/XDP subsystem rebase per RHEL minor release. 5.14 RHEL kernels
+/// carry far newer XDP code than the version number says, but these rows are
+/// unverified estimates, not taken row by row from the release notes. they only
+/// feed `backport_note`, feature gates use the version number and runtime probes.
+const RHEL_BPF_REBASE: &[(MajorMinor, MajorMinor)] = &[
+    ((9, 0), (5, 15)),
+    ((9, 1), (5, 16)),
+    ((9, 2), (6, 0)),
+    ((9, 3), (6, 2)),
+    ((9, 4), (6, 6)),
+    ((9, 5), (6, 8)),
+];
+
+/// where a kernel build comes from, parsed from the release suffix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Distro {
+    /// "5.14.0-427.13.1.el9_4.x86_64" (also CentOS Stream, Rocky, Alma)
+    Rhel { major: u32, minor: Option<u32> },
+    /// "5.15.0-105-generic", "6.8.0-1014-azure"
+    Ubuntu { abi: u32, flavour: String },
+    /// "6.1.0-18-amd64"
+    Debian { abi: u32 },
+    /// no suffix, or one we do not recognise
+    Other,
+}
+
+/// parsed `uname -r`, ordered by (major, minor, patch) only
+#[derive(Debug, Clone)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    /// everything after the first '-', e.g. "105-generic"
+    pub suffix: Option<String>,
+    pub distro: Distro,
+    release: String,
+}
+
+impl KernelVersion {
+    pub fn running() -> Result<Self> {
+        let uname = utsname::uname().context("uname failed")?;
+        let release = uname
+            .release()
+            .to_str()
+            .ok_or_else(|| anyhow!("Kernel release is not valid UTF-8"))?;
+        release.parse()
+    }
+
+    /// feature gate, `major.minor` is the upstream release that added the feature.
+    /// backports are not counted, a distro kernel may have the feature earlier
+    pub fn at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
+    /// human readable note when a RHEL kernel likely carries newer BPF/XDP code
+    /// than its version number
+    pub fn backport_note(&self) -> Option<String> {
+        let Distro::Rhel { major, minor: Some(minor) } = self.distro else {
+            return None;
+        };
+
+        let level = RHEL_BPF_REBASE
+            .iter()
+            .filter(|(rhel, _)| *rhel <= (major, minor) && rhel.0 == major)
+            .map(|(_, level)| *level)
+            .max()
+            .filter(|level| *level > (self.major, self.minor))?;
+
+        Some(format!(
+            "RHEL {}.{} backports BPF/XDP from roughly upstream {}.{} (estimate), checks go by the version number",
+            major, minor, level.0, level.1
+        ))
+    }
+
+    /// "Ubuntu generic, ABI 105" and the like, None for plain upstream builds
+    pub fn build(&self) -> Option<String> {
+        let suffix = self.suffix.as_deref()?;
+        Some(match &self.distro {
+            Distro::Rhel { major, minor: Some(minor) } => format!("RHEL {}.{}", major, minor),
+            Distro::Rhel { major, minor: None } => format!("RHEL {} / CentOS Stream", major),
+            Distro::Ubuntu { abi, flavour } => format!("Ubuntu {}, ABI {}", flavour, abi),
+            Distro::Debian { abi } => format!("Debian, ABI {}", abi),
+            Distro::Other => format!("local suffix '{}'", suffix),
+        })
+    }
+
+    fn parse_distro(suffix: &str) -> Distro {
+        // RHEL: "427.13.1.el9_4.x86_64", CentOS Stream has no "_minor"
+        if let Some(el) = suffix.split('.').find_map(|p| p.strip_prefix("el")) {
+            let mut parts = el.split('_');
+            if let Some(Ok(major)) = parts.next().map(str::parse) {
+                let minor = parts.next().and_then(|m| m.parse().ok());
+                return Distro::Rhel { major, minor };
+            }
+        }
+
+        // Ubuntu and Debian: "<abi>-<flavour>"
+        if let Some((abi, flavour)) = suffix.split_once('-') {
+            if let Ok(abi) = abi.parse() {
+                return match flavour {
+                    "amd64" | "arm64" | "cloud-amd64" | "cloud-arm64" | "rt-amd64" | "rt-arm64" => {
+                        Distro::Debian { abi }
+                    }
+                    _ => Distro::Ubuntu {
+                        abi,
+                        flavour: flavour.to_string(),
+                    },
+                };
+            }
+        }
+
+        Distro::Other
+    }
+}
+
+impl FromStr for KernelVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(release: &str) -> Result<Self> {
+        let release = release.trim();
+        let (version, suffix) = match release.split_once('-') {
+            Some((version, suffix)) => (version, Some(suffix)),
+            None => (release, None),
+        };
+
+        // "6.6.0+" and similar local builds carry junk after the digits
+        let number = |part: Option<&str>| -> Option<u32> {
+            let part = part?;
+            let end = part.find(|c: char| !c.is_ascii_digit()).unwrap_or(part.len());
+            part[..end].parse().ok()
+        };
+
+        let mut parts = version.split('.');
+        let major = number(parts.next())
+            .ok_or_else(|| anyhow!("Unable to parse kernel version: {}", release))?;
+        let minor = number(parts.next())
+            .ok_or_else(|| anyhow!("Unable to parse kernel version: {}", release))?;
+        let patch = number(parts.next()).unwrap_or(0);
+
+        Ok(KernelVersion {
+            major,
+            minor,
+            patch,
+            suffix: suffix.map(str::to_string),
+            distro: suffix.map_or(Distro::Other, Self::parse_distro),
+            release: release.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.release)
+    }
+}
+
+impl Ord for KernelVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
     }
+}
 
-    let major: u32 = parts[0].parse().unwrap_or(0);
-    let minor: u32 = parts[1].parse().unwrap_or(0);
+impl PartialOrd for KernelVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-    let status = if major > MIN_KERNEL_VERSION.0
-        || (major == MIN_KERNEL_VERSION.0 && minor >= MIN_KERNEL_VERSION.1) {
-        if major > RECOMMENDED_KERNEL_VERSION.0
-            || (major == RECOMMENDED_KERNEL_VERSION.0 && minor >= RECOMMENDED_KERNEL_VERSION.1) {
+impl PartialEq for KernelVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for KernelVersion {}
+
+fn check_kernel_version() -> Result<CheckResult> {
+    let kernel = match KernelVersion::running() {
+        Ok(kernel) => kernel,
+        Err(e) => {
+            return Ok(CheckResult {
+                name: "Kernel Version".to_string(),
+                status: CheckStatus::Error,
+                message: e.to_string(),
+                details: None,
+            });
+        }
+    };
+
+    let (major, minor) = (kernel.major, kernel.minor);
+
+    let status = if kernel.at_least(MIN_KERNEL_VERSION.0, MIN_KERNEL_VERSION.1) {
+        if kernel.at_least(RECOMMENDED_KERNEL_VERSION.0, RECOMMENDED_KERNEL_VERSION.1) {
             CheckStatus::Pass
         } else {
             CheckStatus::Warning
@@ -59,7 +242,10 @@ fn check_kernel_version() -> Result<CheckResult> {
         CheckStatus::Fail
     };
 
-    let message = format!("Kernel version: {} ({}.{})", release, major, minor);
+    let message = format!(
+        "Kernel version: {} ({}.{}.{})",
+        kernel, kernel.major, kernel.minor, kernel.patch
+    );
     let details = match status {
         CheckStatus::Pass => Some(format!(
             "Kernel {}.{} meets recommended version {}.{} for stable AF_XDP support",
@@ -75,6 +261,15 @@ fn check_kernel_version() -> Result<CheckResult> {
         )),
         _ => None,
     };
+    let details = details.map(|d| {
+        let mut lines = Vec::new();
+        if let Some(build) = kernel.build() {
+            lines.push(format!("Build: {}", build));
+        }
+        lines.extend(kernel.backport_note());
+        lines.push(d);
+        lines.join("\n")
+    });
 
     Ok(CheckResult {
         name: "Kernel Version".to_string(),
@@ -84,59 +279,137 @@ fn check_kernel_version() -> Result<CheckResult> {
     })
 }
 
-fn check_kernel_config() -> Result<Vec<CheckResult>> {
-    let mut results = Vec::new();
+/// value of one kernel config option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigValue {
+    BuiltIn,
+    Module,
+    /// `# CONFIG_X is not set`
+    NotSet,
+    /// strings and numbers, quotes kept as in the file
+    Value(String),
+}
 
-    // different kernel config locations
-    let config_paths = [
-        format!("/boot/config-{}", utsname::uname()?.release().to_str().unwrap_or("")),
-        "/proc/config.gz".to_string(),
-        "/boot/config".to_string(),
-    ];
+impl ConfigValue {
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, ConfigValue::BuiltIn | ConfigValue::Module)
+    }
+}
+
+impl fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigValue::BuiltIn => f.write_str("y"),
+            ConfigValue::Module => f.write_str("m"),
+            ConfigValue::NotSet => f.write_str("is not set"),
+            ConfigValue::Value(value) => f.write_str(value),
+        }
+    }
+}
 
-    let mut config_content = None;
-    let mut config_path = None;
-
-    for path in &config_paths {
-        if Path::new(path).exists() {
-            if path.ends_with(".gz") {
-                // Handle compressed config
-                use std::process::Command;
-                let output = Command::new("zcat")
-                    .arg(path)
-                    .output()
-                    .context("Failed to decompress kernel config")?;
-
-                if output.status.success() {
-                    config_content = Some(String::from_utf8_lossy(&output.stdout).to_string());
-                    config_path = Some(path.clone());
-                    break;
+/// parsed kernel .config
+pub struct KernelConfig {
+    /// file the options came from
+    pub source: String,
+    options: HashMap<String, ConfigValue>,
+}
+
+impl KernelConfig {
+    /// first readable config of the running kernel, None when there is none
+    pub fn load() -> Result<Option<Self>> {
+        let release = utsname::uname()?.release().to_string_lossy().into_owned();
+
+        // different kernel config locations
+        let config_paths = [
+            format!("/boot/config-{}", release),
+            "/proc/config.gz".to_string(),
+            "/boot/config".to_string(),
+        ];
+
+        for path in &config_paths {
+            if !Path::new(path).exists() {
+                continue;
+            }
+
+            let content = if path.ends_with(".gz") {
+                let mut content = String::new();
+                let read = fs::File::open(path).and_then(|file| GzDecoder::new(file).read_to_string(&mut content));
+                match read {
+                    Ok(_) => content,
+                    Err(e) => {
+                        log::debug!("Cannot decompress {}: {}", path, e);
+                        continue;
+                    }
                 }
             } else {
                 match fs::read_to_string(path) {
-                    Ok(content) => {
-                        config_content = Some(content);
-                        config_path = Some(path.clone());
-                        break;
+                    Ok(content) => content,
+                    Err(e) => {
+                        log::debug!("Cannot read {}: {}", path, e);
+                        continue;
                     }
-                    Err(_) => continue,
                 }
+            };
+
+            return Ok(Some(Self::parse(&content, path)));
+        }
+
+        Ok(None)
+    }
+
+    fn parse(content: &str, source: &str) -> Self {
+        let mut options = HashMap::new();
+
+        for line in content.lines().map(str::trim) {
+            if let Some(name) = line
+                .strip_prefix("# ")
+                .and_then(|rest| rest.strip_suffix(" is not set"))
+            {
+                options.insert(name.to_string(), ConfigValue::NotSet);
+            } else if let Some((name, value)) = line.split_once('=').filter(|_| !line.starts_with('#')) {
+                let value = match value {
+                    "y" => ConfigValue::BuiltIn,
+                    "m" => ConfigValue::Module,
+                    "n" => ConfigValue::NotSet,
+                    other => ConfigValue::Value(other.to_string()),
+                };
+                options.insert(name.to_string(), value);
             }
         }
+
+        KernelConfig {
+            source: source.to_string(),
+            options,
+        }
     }
 
-    if config_content.is_none() {
-        results.push(CheckResult {
-            name: "Kernel Config".to_string(),
-            status: CheckStatus::Warning,
-            message: "Unable to find kernel configuration file".to_string(),
-            details: Some("Cannot verify XDP-related kernel options. They might still be enabled.".to_string()),
-        });
-        return Ok(results);
+    /// None means the option does not exist in this kernel's Kconfig
+    pub fn get(&self, option: &str) -> Option<&ConfigValue> {
+        self.options.get(option)
+    }
+
+    pub fn is_enabled(&self, option: &str) -> bool {
+        self.get(option).is_some_and(ConfigValue::is_enabled)
+    }
+}
+
+fn missing_config_result() -> CheckResult {
+    CheckResult {
+        name: "Kernel Config".to_string(),
+        status: CheckStatus::Warning,
+        message: "Unable to find kernel configuration file".to_string(),
+        details: Some("Cannot verify XDP-related kernel options. The BPF feature probes below test them directly.".to_string()),
     }
+}
 
-    let config = config_content.unwrap();
-    let source = config_path.unwrap();
+fn check_kernel_config() -> Result<Vec<CheckResult>> {
+    let mut results = Vec::new();
+
+    let Some(config) = KernelConfig::load()? else {
+        results.push(missing_config_result());
+        return Ok(results);
+    };
+    let source = &config.source;
 
     // Check required kernel options
     let required_options = [
@@ -147,11 +420,10 @@ fn check_kernel_config() -> Result<Vec<CheckResult>> {
     ];
 
     for (option, description) in &required_options {
-        let enabled = config.contains(&format!("{}=y", option))
-            || config.contains(&format!("{}=m", option));
+        let enabled = config.is_enabled(option);
 
         results.push(CheckResult {
-            name: format!("{}", option),
+            name: option.to_string(),
             status: if enabled { CheckStatus::Pass } else { CheckStatus::Fail },
             message: description.to_string(),
             details: if enabled {
@@ -169,11 +441,10 @@ fn check_kernel_config() -> Result<Vec<CheckResult>> {
     ];
 
     for (option, description) in &optional_options {
-        let enabled = config.contains(&format!("{}=y", option))
-            || config.contains(&format!("{}=m", option));
+        let enabled = config.is_enabled(option);
 
         results.push(CheckResult {
-            name: format!("{}", option),
+            name: option.to_string(),
             status: if enabled { CheckStatus::Pass } else { CheckStatus::Warning },
             message: description.to_string(),
             details: if enabled {
@@ -187,6 +458,45 @@ fn check_kernel_config() -> Result<Vec<CheckResult>> {
     Ok(results)
 }
 
+/// look up arbitrary options, "XDP_SOCKETS" and "CONFIG_XDP_SOCKETS" both work
+pub fn query_kernel_config(options: &[String]) -> Result<Vec<CheckResult>> {
+    if options.is_empty() {
+        return check_kernel_config();
+    }
+
+    let Some(config) = KernelConfig::load()? else {
+        return Ok(vec![missing_config_result()]);
+    };
+
+    let results = options
+        .iter()
+        .map(|option| {
+            let option = if option.starts_with("CONFIG_") {
+                option.clone()
+            } else {
+                format!("CONFIG_{}", option)
+            };
+
+            let (status, message) = match config.get(&option) {
+                Some(ConfigValue::BuiltIn) => (CheckStatus::Pass, format!("{}=y (built-in)", option)),
+                Some(ConfigValue::Module) => (CheckStatus::Pass, format!("{}=m (module)", option)),
+                Some(ConfigValue::NotSet) => (CheckStatus::Warning, format!("{} is not set", option)),
+                Some(ConfigValue::Value(value)) => (CheckStatus::Info, format!("{}={}", option, value)),
+                None => (CheckStatus::Warning, format!("{} not present", option)),
+            };
+
+            CheckResult {
+                name: option,
+                status,
+                message,
+                details: Some(format!("From {}", config.source)),
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
+
 fn check_btf_support() -> CheckResult {
     let btf_path = "/sys/kernel/btf/vmlinux";
 
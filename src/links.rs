@@ -0,0 +1,291 @@
+use aya::maps::MapInfo;
+use aya::programs::{loaded_programs, ProgramInfo};
+use std::collections::HashMap;
+
+use crate::bpf::{self, LinkInfo, LinkTarget};
+use crate::netlink::{self, XdpAttachment};
+use crate::output::{CheckResult, CheckStatus};
+
+/// program name libxdp gives its multi-prog dispatcher
+const DISPATCHER_NAME: &str = "xdp_dispatcher";
+
+/// libxdp MAX_DISPATCHER_ACTIONS, the number of component slots
+const MAX_DISPATCHER_ACTIONS: usize = 10;
+/// first byte of a versioned struct xdp_dispatcher_config
+const XDP_DISPATCHER_MAGIC: u8 = 236;
+/// chain_call_actions bit libxdp sets to continue on the program's own return value
+const XDP_DISPATCHER_RETVAL: u32 = 31;
+
+const XDP_ACTIONS: &[(u32, &str)] = &[
+    (0, "ABORTED"),
+    (1, "DROP"),
+    (2, "PASS"),
+    (3, "TX"),
+    (4, "REDIRECT"),
+    (XDP_DISPATCHER_RETVAL, "RETVAL"),
+];
+
+/// struct xdp_dispatcher_config from the dispatcher's .rodata
+struct DispatcherConfig {
+    /// None for the unversioned config of libxdp before 1.2
+    version: Option<u8>,
+    num_progs_enabled: u8,
+    is_xdp_frags: bool,
+    chain_call_actions: [u32; MAX_DISPATCHER_ACTIONS],
+    run_prios: [u32; MAX_DISPATCHER_ACTIONS],
+}
+
+impl DispatcherConfig {
+    fn parse(data: &[u8]) -> Option<Self> {
+        // both layouts put the u32 arrays at offset 4
+        let array = |index: usize| -> Option<[u32; MAX_DISPATCHER_ACTIONS]> {
+            let start = 4 + index * MAX_DISPATCHER_ACTIONS * 4;
+            let bytes = data.get(start..start + MAX_DISPATCHER_ACTIONS * 4)?;
+            let mut out = [0u32; MAX_DISPATCHER_ACTIONS];
+            for (value, chunk) in out.iter_mut().zip(bytes.chunks_exact(4)) {
+                *value = u32::from_ne_bytes(chunk.try_into().unwrap());
+            }
+            Some(out)
+        };
+
+        let (version, num_progs_enabled, is_xdp_frags) = if data.first() == Some(&XDP_DISPATCHER_MAGIC) {
+            (Some(*data.get(1)?), *data.get(2)?, *data.get(3)? != 0)
+        } else {
+            (None, *data.first()?, false)
+        };
+
+        Some(DispatcherConfig {
+            version,
+            num_progs_enabled,
+            is_xdp_frags,
+            chain_call_actions: array(0)?,
+            run_prios: array(1)?,
+        })
+    }
+}
+
+fn describe_actions(mask: u32) -> String {
+    let actions: Vec<&str> = XDP_ACTIONS
+        .iter()
+        .filter(|(bit, _)| mask & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if actions.is_empty() {
+        "none".to_string()
+    } else {
+        actions.join(", ")
+    }
+}
+
+/// XDP links per interface, and the components of libxdp dispatchers
+pub fn check_links(interface: Option<&str>) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let links = match bpf::links() {
+        Ok(links) => links,
+        Err(e) => {
+            results.push(CheckResult {
+                name: "BPF Links".to_string(),
+                status: CheckStatus::Info,
+                message: "Unable to enumerate BPF links".to_string(),
+                details: Some(format!("{:#}\nBPF links need kernel 5.8+ and CAP_SYS_ADMIN", e)),
+            });
+            return results;
+        }
+    };
+
+    let attachments: Vec<XdpAttachment> = match netlink::xdp_attachments() {
+        Ok(attachments) => attachments,
+        Err(e) => {
+            log::debug!("RTM_GETLINK dump failed: {:#}", e);
+            Vec::new()
+        }
+    }
+    .into_iter()
+    .filter(|a| interface.is_none_or(|filter| filter == a.name))
+    .collect();
+
+    let programs: HashMap<u32, ProgramInfo> = loaded_programs()
+        .filter_map(|r| r.ok())
+        .map(|p| (p.id(), p))
+        .collect();
+    let prog_name = |id: u32| -> &str {
+        programs
+            .get(&id)
+            .and_then(|p| p.name_as_str())
+            .filter(|n| !n.is_empty())
+            .unwrap_or("?")
+    };
+
+    for attachment in &attachments {
+        for (mode, prog_id) in attachment.programs() {
+            let link = links.iter().find(|l| {
+                l.prog_id == prog_id && matches!(l.target, LinkTarget::Xdp { ifindex } if ifindex == attachment.ifindex)
+            });
+
+            results.push(match link {
+                Some(link) => CheckResult {
+                    name: format!("{}: XDP Link", attachment.name),
+                    status: CheckStatus::Pass,
+                    message: format!("{} (ID: {}) held by bpf_link {}", prog_name(prog_id), prog_id, link.id),
+                    details: Some(format!(
+                        "Link ID: {}\nIfindex: {}\nMode: {}\nOther loaders cannot replace it while the link exists",
+                        link.id,
+                        attachment.ifindex,
+                        mode.as_str()
+                    )),
+                },
+                None => CheckResult {
+                    name: format!("{}: XDP Link", attachment.name),
+                    status: CheckStatus::Info,
+                    message: format!("{} (ID: {}) attached through netlink, no bpf_link", prog_name(prog_id), prog_id),
+                    details: Some("Any process with CAP_NET_ADMIN can silently replace a netlink attachment".to_string()),
+                },
+            });
+
+            if prog_name(prog_id) == DISPATCHER_NAME {
+                results.extend(check_dispatcher(&attachment.name, prog_id, programs.get(&prog_id), &links, &prog_name));
+            }
+        }
+    }
+
+    if interface.is_none() {
+        let xdp_links = links.iter().filter(|l| matches!(l.target, LinkTarget::Xdp { .. }));
+        for link in xdp_links {
+            let LinkTarget::Xdp { ifindex } = link.target else { continue };
+            // ifindex 0 is a link whose interface went away
+            if ifindex == 0 || attachments.iter().all(|a| a.ifindex != ifindex) {
+                results.push(CheckResult {
+                    name: format!("BPF Link {}", link.id),
+                    status: CheckStatus::Warning,
+                    message: format!(
+                        "{} link for {} (ID: {}) is not attached to any interface",
+                        link.type_name(),
+                        prog_name(link.prog_id),
+                        link.prog_id
+                    ),
+                    details: Some(format!(
+                        "Ifindex: {}\nThe interface was removed; the link keeps the program loaded until its last fd or pin is gone",
+                        ifindex
+                    )),
+                });
+            }
+        }
+    }
+
+    results
+}
+
+/// components of a libxdp dispatcher: freplace links targeting it, with the
+/// run priority and chain-call actions of their slot
+fn check_dispatcher<'a>(
+    iface: &str,
+    dispatcher_id: u32,
+    dispatcher: Option<&ProgramInfo>,
+    links: &[LinkInfo],
+    prog_name: &dyn Fn(u32) -> &'a str,
+) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let config = dispatcher.and_then(dispatcher_config);
+    let btf_id = dispatcher.and_then(|d| d.btf_id());
+
+    // (slot, link) for every freplace link into this dispatcher
+    let mut components: Vec<(Option<usize>, &LinkInfo)> = links
+        .iter()
+        .filter_map(|link| match link.target {
+            LinkTarget::Tracing { target_obj_id, target_btf_id } if target_obj_id == dispatcher_id => {
+                // the replaced functions are named prog0 .. prog9
+                let slot = btf_id
+                    .and_then(|btf_id| bpf::btf_type_name(btf_id, target_btf_id).ok())
+                    .and_then(|name| name.strip_prefix("prog")?.parse::<usize>().ok())
+                    .filter(|slot| *slot < MAX_DISPATCHER_ACTIONS);
+                Some((slot, link))
+            }
+            _ => None,
+        })
+        .collect();
+    components.sort_by_key(|(slot, _)| *slot);
+
+    let mut details = format!("Dispatcher ID: {}", dispatcher_id);
+    match &config {
+        Some(config) => {
+            details.push_str(&format!(
+                "\nConfig version: {}\nEnabled slots: {}\nFrags: {}",
+                config.version.map_or("1 (unversioned)".to_string(), |v| v.to_string()),
+                config.num_progs_enabled,
+                if config.is_xdp_frags { "yes" } else { "no" }
+            ));
+        }
+        None => details.push_str("\nDispatcher config (.rodata) not readable"),
+    }
+
+    let enabled = config.as_ref().map(|c| c.num_progs_enabled as usize);
+    let mismatch = enabled.is_some_and(|n| n != components.len());
+
+    results.push(CheckResult {
+        name: format!("{}: XDP Dispatcher", iface),
+        status: if mismatch { CheckStatus::Warning } else { CheckStatus::Pass },
+        message: if mismatch {
+            format!(
+                "{} (ID: {}) has {} slot(s) enabled but {} component link(s)",
+                DISPATCHER_NAME,
+                dispatcher_id,
+                enabled.unwrap_or(0),
+                components.len()
+            )
+        } else {
+            format!("{} (ID: {}) running {} component(s)", DISPATCHER_NAME, dispatcher_id, components.len())
+        },
+        details: Some(details),
+    });
+
+    for (slot, link) in components {
+        let name = prog_name(link.prog_id);
+        let (message, slot_details) = match (slot, &config) {
+            (Some(slot), Some(config)) => (
+                format!(
+                    "{} (ID: {}) priority {}, chain on {}",
+                    name,
+                    link.prog_id,
+                    config.run_prios[slot],
+                    describe_actions(config.chain_call_actions[slot])
+                ),
+                format!("Slot: prog{}\nLink ID: {}", slot, link.id),
+            ),
+            (Some(slot), None) => (
+                format!("{} (ID: {})", name, link.prog_id),
+                format!("Slot: prog{}\nLink ID: {}", slot, link.id),
+            ),
+            (None, _) => (
+                format!("{} (ID: {})", name, link.prog_id),
+                format!("Slot: unknown\nLink ID: {}", link.id),
+            ),
+        };
+
+        results.push(CheckResult {
+            name: format!("{}: Dispatcher Component", iface),
+            status: CheckStatus::Info,
+            message,
+            details: Some(slot_details),
+        });
+    }
+
+    results
+}
+
+/// xdp_dispatcher_config from the dispatcher's read-only data map
+fn dispatcher_config(dispatcher: &ProgramInfo) -> Option<DispatcherConfig> {
+    let map_ids = dispatcher.map_ids().ok()??;
+
+    map_ids.into_iter().find_map(|id| {
+        let map = MapInfo::from_id(id).ok()?;
+        if !map.name_as_str()?.ends_with(".rodata") {
+            return None;
+        }
+        let value = bpf::map_lookup(id, &0u32.to_ne_bytes(), map.value_size() as usize)
+            .map_err(|e| log::debug!("{:#}", e))
+            .ok()?;
+        DispatcherConfig::parse(&value)
+    })
+}
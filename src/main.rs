@@ -1,14 +1,28 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::time::Duration;
 
+mod bpf;
+mod bpffs;
 mod capabilities;
+mod drivers;
+mod elf;
+mod ethtool;
+mod expect;
 mod kernel;
+mod links;
+mod netlink;
 mod nic;
 mod output;
+mod probe;
+mod prog;
 mod runtime;
 mod system;
+mod watch;
+mod xsk;
 
 #[derive(Parser)]
 #[command(name = "xdp-check")]
@@ -21,6 +35,15 @@ struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
+    /// driver knowledge base (TOML) to use instead of the embedded one
+    #[arg(long, global = true, value_name = "PATH")]
+    driver_db: Option<PathBuf>,
+
+    /// what interrupt coalescing and busy polling are checked against, without
+    /// one they are only reported
+    #[arg(long, global = true, value_enum)]
+    profile: Option<system::TuningProfile>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -38,30 +61,124 @@ enum Commands {
         #[arg(long)]
         skip_runtime: bool,
     },
-    Kernel,
+    Kernel {
+        #[command(subcommand)]
+        command: Option<KernelCommands>,
+    },
     Nic {
         /// (e.g., eth0, ens3)
         interface: String,
+        /// warn when the RX ring has fewer descriptors than this
+        #[arg(long, value_name = "N", default_value_t = nic::DEFAULT_MIN_RX_RING)]
+        min_rx_ring: u32,
     },
     /// verify if XDP is currently active on the system
     Runtime {
         interface: Option<String>,
+        /// program that must be loaded, repeatable (default: agave_xdp, optional)
+        #[arg(long = "program", value_name = "NAME")]
+        programs: Vec<String>,
+        /// TOML file of expected programs with per-program rules
+        #[arg(long, value_name = "PATH")]
+        programs_config: Option<PathBuf>,
+        /// fail unless the --program programs have this tag (16 hex digits, as shown by bpftool)
+        #[arg(long, value_name = "HEX")]
+        expect_tag: Option<String>,
+        /// fail unless the --program programs have the tag of the same-named program in this ELF object
+        #[arg(long, value_name = "PATH", conflicts_with = "expect_tag")]
+        expect_elf: Option<PathBuf>,
+        /// mount bpffs at /sys/fs/bpf when it is missing
+        #[arg(long)]
+        fix: bool,
     },
     Quick,
+    /// show a loaded BPF program's maps, instruction counts, run stats and BTF functions
+    Prog {
+        /// program ID or name (e.g. agave_xdp)
+        program: String,
+    },
+    /// load and attach a test XDP program and bind an AF_XDP socket on a throwaway veth pair
+    Probe {
+        /// bind the AF_XDP socket to this interface instead of the scratch veth.
+        /// a zero-copy bind may reset the queue on a live NIC
+        #[arg(long)]
+        interface: Option<String>,
+        /// queue to bind when --interface is given
+        #[arg(long, default_value_t = 0)]
+        queue: u32,
+    },
+    /// sample XDP queue counters and AF_XDP socket stats and print rates, like top
+    Watch {
+        /// interface to watch (default: every interface with XDP attached or an AF_XDP socket)
+        interface: Option<String>,
+        /// sampling interval
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        interval: u64,
+        /// stop after this many samples
+        #[arg(long)]
+        count: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KernelCommands {
+    /// show XDP-related kernel config options, or query any option
+    Config {
+        /// option to look up, repeatable (e.g. CONFIG_XDP_SOCKETS or XDP_SOCKETS)
+        #[arg(long = "option", value_name = "CONFIG_FOO")]
+        options: Vec<String>,
+    },
 }
 
 fn main() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        // newer kernels send IFLA_AF_SPEC fields the crate does not know about
+        .filter_module("netlink_packet_route", log::LevelFilter::Error)
         .init();
 
     let cli = Cli::parse();
 
+    if let Err(e) = drivers::init(cli.driver_db.as_deref()) {
+        eprintln!("{} {:#}", "Error:".red().bold(), e);
+        process::exit(1);
+    }
+
     let result = match cli.command {
         None | Some(Commands::Check { .. }) => run_full_check(&cli),
-        Some(Commands::Kernel) => run_kernel_check(&cli),
-        Some(Commands::Nic { ref interface }) => run_nic_check(&cli, interface),
-        Some(Commands::Runtime { ref interface }) => run_runtime_check(&cli, interface.as_deref()),
+        Some(Commands::Kernel { command: None }) => run_kernel_check(&cli),
+        Some(Commands::Kernel {
+            command: Some(KernelCommands::Config { ref options }),
+        }) => run_kernel_config(&cli, options),
+        Some(Commands::Nic { ref interface, min_rx_ring }) => run_nic_check(&cli, interface, min_rx_ring),
+        Some(Commands::Runtime {
+            ref interface,
+            ref programs,
+            ref programs_config,
+            ref expect_tag,
+            ref expect_elf,
+            fix,
+        }) => run_runtime_check(
+            &cli,
+            interface.as_deref(),
+            programs,
+            programs_config.as_deref(),
+            expect_tag.as_deref(),
+            expect_elf.as_deref(),
+            fix,
+        ),
         Some(Commands::Quick) => run_quick_check(&cli),
+        Some(Commands::Prog { ref program }) => run_prog(&cli, program),
+        Some(Commands::Probe { ref interface, queue }) => run_probe(&cli, interface.as_deref(), queue),
+        Some(Commands::Watch {
+            ref interface,
+            interval,
+            count,
+        }) => watch::watch(
+            interface.as_deref(),
+            Duration::from_millis(interval.max(1)),
+            count,
+            matches!(cli.format, OutputFormat::Json),
+        ),
     };
 
     if let Err(e) = result {
@@ -86,16 +203,16 @@ fn run_full_check(cli: &Cli) -> Result<()> {
     report.add_section("Capabilities", cap_results);
 
     println!("{}", "Checking system resources...".yellow());
-    let sys_results = system::check_system_resources()?;
+    let sys_results = system::check_system_resources(cli.profile)?;
     report.add_section("System Resources", sys_results);
 
     println!("{}", "Checking network interfaces...".yellow());
-    let nic_results = nic::check_all_interfaces()?;
+    let nic_results = nic::check_all_interfaces(cli.profile)?;
     report.add_section("Network Interfaces", nic_results);
 
     if !matches!(cli.command, Some(Commands::Check { skip_runtime: true, .. })) {
         println!("{}", "Checking XDP runtime status...".yellow());
-        let runtime_results = runtime::check_xdp_runtime(None)?;
+        let runtime_results = runtime::check_xdp_runtime(None, &expect::expected_programs(&[], None, None, None)?, false)?;
         report.add_section("Runtime Status", runtime_results);
     }
 
@@ -134,14 +251,36 @@ fn run_kernel_check(cli: &Cli) -> Result<()> {
     Ok(())
 }
 
-fn run_nic_check(cli: &Cli, interface: &str) -> Result<()> {
+fn run_kernel_config(cli: &Cli, options: &[String]) -> Result<()> {
+    let mut report = output::Report::new();
+
+    println!("{}", "Kernel Config".cyan().bold());
+    println!("{}", "=============".cyan());
+    println!();
+
+    let config_results = kernel::query_kernel_config(options)?;
+    report.add_section("Kernel Config", config_results);
+
+    match cli.format {
+        OutputFormat::Human => report.print_human(cli.verbose),
+        OutputFormat::Json => report.print_json()?,
+    }
+
+    if !report.is_compatible() {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn run_nic_check(cli: &Cli, interface: &str, min_rx_ring: u32) -> Result<()> {
     let mut report = output::Report::new();
 
     println!("{}", format!("NIC Compatibility Check: {}", interface).cyan().bold());
     println!("{}", "================================".cyan());
     println!();
 
-    let nic_results = nic::check_interface(interface)?;
+    let nic_results = nic::check_interface(interface, min_rx_ring, cli.profile)?;
     report.add_section(&format!("Interface: {}", interface), nic_results);
 
     match cli.format {
@@ -156,14 +295,26 @@ fn run_nic_check(cli: &Cli, interface: &str) -> Result<()> {
     Ok(())
 }
 
-fn run_runtime_check(cli: &Cli, interface: Option<&str>) -> Result<()> {
+fn run_runtime_check(
+    cli: &Cli,
+    interface: Option<&str>,
+    programs: &[String],
+    programs_config: Option<&Path>,
+    expect_tag: Option<&str>,
+    expect_elf: Option<&Path>,
+    fix: bool,
+) -> Result<()> {
     let mut report = output::Report::new();
 
+    let expected = expect::expected_programs(programs, programs_config, expect_tag, expect_elf)?;
+    // only explicit expectations turn the status report into a gate
+    let gate = !programs.is_empty() || programs_config.is_some() || expect_tag.is_some() || expect_elf.is_some();
+
     println!("{}", "XDP Runtime Status Check".cyan().bold());
     println!("{}", "========================".cyan());
     println!();
 
-    let runtime_results = runtime::check_xdp_runtime(interface)?;
+    let runtime_results = runtime::check_xdp_runtime(interface, &expected, fix)?;
     report.add_section("Runtime Status", runtime_results);
 
     match cli.format {
@@ -171,6 +322,10 @@ fn run_runtime_check(cli: &Cli, interface: Option<&str>) -> Result<()> {
         OutputFormat::Json => report.print_json()?,
     }
 
+    if gate && !report.is_compatible() {
+        process::exit(1);
+    }
+
     Ok(())
 }
 
@@ -200,4 +355,47 @@ fn run_quick_check(cli: &Cli) -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+fn run_prog(cli: &Cli, program: &str) -> Result<()> {
+    let mut report = output::Report::new();
+
+    println!("{}", format!("BPF Program: {}", program).cyan().bold());
+    println!("{}", "================================".cyan());
+    println!();
+
+    let prog_results = prog::inspect_program(program)?;
+    report.add_section(&format!("Program: {}", program), prog_results);
+
+    match cli.format {
+        OutputFormat::Human => report.print_human(cli.verbose),
+        OutputFormat::Json => report.print_json()?,
+    }
+
+    Ok(())
+}
+
+fn run_probe(cli: &Cli, interface: Option<&str>, queue: u32) -> Result<()> {
+    let mut report = output::Report::new();
+
+    println!("{}", "XDP Capability Probe".cyan().bold());
+    println!("{}", "====================".cyan());
+    println!();
+
+    let probe_results = probe::probe_xdp()?;
+    report.add_section("XDP Probe", probe_results);
+
+    let xsk_results = probe::probe_xsk(interface, queue)?;
+    report.add_section("AF_XDP Probe", xsk_results);
+
+    match cli.format {
+        OutputFormat::Human => report.print_human(cli.verbose),
+        OutputFormat::Json => report.print_json()?,
+    }
+
+    if !report.is_compatible() {
+        process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,431 @@
+use anyhow::{Context, Result};
+use futures::TryStreamExt;
+use netlink_packet_route::link::{LinkAttribute, LinkXdp, XdpAttached};
+use netlink_sys::protocols::NETLINK_GENERIC;
+use netlink_sys::{Socket, SocketAddr};
+use rtnetlink::{LinkUnspec, LinkVeth};
+use std::future::Future;
+use std::io;
+
+pub const NLM_F_REQUEST: u16 = 0x01;
+pub const NLM_F_DUMP: u16 = 0x300;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLMSG_HDRLEN: usize = 16;
+
+// linux/genetlink.h
+const GENL_ID_CTRL: u16 = 0x10;
+const GENL_HDRLEN: usize = 4;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+// linux/netdev.h
+const NETDEV_CMD_DEV_GET: u8 = 1;
+const NETDEV_A_DEV_IFINDEX: u16 = 1;
+const NETDEV_A_DEV_XDP_FEATURES: u16 = 3;
+const NETDEV_A_DEV_XDP_ZC_MAX_SEGS: u16 = 4;
+
+/// nla_type flag of an attribute that holds attributes
+pub const NLA_F_NESTED: u16 = 1 << 15;
+
+/// enum netdev_xdp_act
+pub const NETDEV_XDP_ACT_BASIC: u64 = 1 << 0;
+pub const NETDEV_XDP_ACT_REDIRECT: u64 = 1 << 1;
+pub const NETDEV_XDP_ACT_NDO_XMIT: u64 = 1 << 2;
+pub const NETDEV_XDP_ACT_XSK_ZEROCOPY: u64 = 1 << 3;
+pub const NETDEV_XDP_ACT_HW_OFFLOAD: u64 = 1 << 4;
+pub const NETDEV_XDP_ACT_RX_SG: u64 = 1 << 5;
+pub const NETDEV_XDP_ACT_NDO_XMIT_SG: u64 = 1 << 6;
+
+const XDP_ACT_NAMES: &[(u64, &str)] = &[
+    (NETDEV_XDP_ACT_BASIC, "basic"),
+    (NETDEV_XDP_ACT_REDIRECT, "redirect"),
+    (NETDEV_XDP_ACT_NDO_XMIT, "ndo-xmit"),
+    (NETDEV_XDP_ACT_XSK_ZEROCOPY, "xsk-zerocopy"),
+    (NETDEV_XDP_ACT_HW_OFFLOAD, "hw-offload"),
+    (NETDEV_XDP_ACT_RX_SG, "rx-sg"),
+    (NETDEV_XDP_ACT_NDO_XMIT_SG, "ndo-xmit-sg"),
+];
+
+/// XDP attach mode as reported by IFLA_XDP_ATTACHED
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XdpMode {
+    None,
+    Native,
+    Generic,
+    Offload,
+    Multi,
+}
+
+impl XdpMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            XdpMode::None => "none",
+            XdpMode::Native => "native",
+            XdpMode::Generic => "generic",
+            XdpMode::Offload => "offload",
+            XdpMode::Multi => "multi",
+        }
+    }
+}
+
+/// driver-advertised XDP capabilities from the netdev genetlink family (6.3+)
+#[derive(Debug, Clone, Copy)]
+pub struct XdpFeatures {
+    pub flags: u64,
+    pub zc_max_segs: Option<u32>,
+}
+
+impl XdpFeatures {
+    pub fn has(&self, flag: u64) -> bool {
+        self.flags & flag != 0
+    }
+
+    pub fn supported(&self) -> Vec<&'static str> {
+        XDP_ACT_NAMES
+            .iter()
+            .filter(|(flag, _)| self.has(*flag))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    pub fn details(&self) -> String {
+        let mut lines: Vec<String> = XDP_ACT_NAMES
+            .iter()
+            .map(|(flag, name)| format!("{}: {}", name, if self.has(*flag) { "yes" } else { "no" }))
+            .collect();
+
+        if let Some(segs) = self.zc_max_segs {
+            lines.push(format!("xdp-zc-max-segs: {}", segs));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// XDP state of one interface from an RTM_GETLINK dump
+#[derive(Debug, Clone)]
+pub struct XdpAttachment {
+    pub ifindex: u32,
+    pub name: String,
+    pub mode: XdpMode,
+    /// IFLA_XDP_PROG_ID, only set when a single mode is attached
+    pub prog_id: Option<u32>,
+    pub drv_prog_id: Option<u32>,
+    pub skb_prog_id: Option<u32>,
+    pub hw_prog_id: Option<u32>,
+}
+
+impl XdpAttachment {
+    pub fn is_attached(&self) -> bool {
+        self.mode != XdpMode::None
+    }
+
+    /// every attached program with the mode it is attached in
+    pub fn programs(&self) -> Vec<(XdpMode, u32)> {
+        let mut programs = Vec::new();
+
+        if let Some(id) = self.drv_prog_id {
+            programs.push((XdpMode::Native, id));
+        }
+        if let Some(id) = self.skb_prog_id {
+            programs.push((XdpMode::Generic, id));
+        }
+        if let Some(id) = self.hw_prog_id {
+            programs.push((XdpMode::Offload, id));
+        }
+
+        // older kernels only report IFLA_XDP_PROG_ID
+        if programs.is_empty() {
+            if let Some(id) = self.prog_id {
+                programs.push((self.mode, id));
+            }
+        }
+
+        programs
+    }
+
+    pub fn details(&self) -> String {
+        let id = |id: Option<u32>| id.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string());
+
+        format!(
+            "Ifindex: {}\nAttach mode: {}\nProgram ID: {}\nDriver prog ID: {}\nSKB prog ID: {}\nHW prog ID: {}",
+            self.ifindex,
+            self.mode.as_str(),
+            id(self.prog_id),
+            id(self.drv_prog_id),
+            id(self.skb_prog_id),
+            id(self.hw_prog_id),
+        )
+    }
+}
+
+/// run an rtnetlink request on a throwaway current-thread runtime
+fn with_handle<F, Fut, T>(f: F) -> Result<T>
+where
+    F: FnOnce(rtnetlink::Handle) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to create tokio runtime")?;
+
+    rt.block_on(async {
+        let (connection, handle, _) = rtnetlink::new_connection()
+            .context("Failed to open rtnetlink socket")?;
+        tokio::spawn(connection);
+
+        f(handle).await
+    })
+}
+
+/// dump IFLA_XDP for every interface
+pub fn xdp_attachments() -> Result<Vec<XdpAttachment>> {
+    with_handle(|handle| async move {
+        let mut attachments = Vec::new();
+        let mut links = handle.link().get().execute();
+
+        while let Some(msg) = links.try_next().await.context("RTM_GETLINK dump failed")? {
+            let mut attachment = XdpAttachment {
+                ifindex: msg.header.index,
+                name: String::new(),
+                mode: XdpMode::None,
+                prog_id: None,
+                drv_prog_id: None,
+                skb_prog_id: None,
+                hw_prog_id: None,
+            };
+
+            for attr in msg.attributes {
+                match attr {
+                    LinkAttribute::IfName(name) => attachment.name = name,
+                    LinkAttribute::Xdp(xdp) => {
+                        for nla in xdp {
+                            match nla {
+                                LinkXdp::Attached(mode) => attachment.mode = match mode {
+                                    XdpAttached::None => XdpMode::None,
+                                    XdpAttached::Driver => XdpMode::Native,
+                                    XdpAttached::SocketBuffer => XdpMode::Generic,
+                                    XdpAttached::Hardware => XdpMode::Offload,
+                                    _ => XdpMode::Multi,
+                                },
+                                LinkXdp::ProgId(id) => attachment.prog_id = Some(id),
+                                LinkXdp::DrvProgId(id) => attachment.drv_prog_id = Some(id),
+                                LinkXdp::SkbProgId(id) => attachment.skb_prog_id = Some(id),
+                                LinkXdp::HwProgId(id) => attachment.hw_prog_id = Some(id),
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            attachments.push(attachment);
+        }
+
+        Ok(attachments)
+    })
+}
+
+pub fn xdp_attachment(interface: &str) -> Result<Option<XdpAttachment>> {
+    Ok(xdp_attachments()?.into_iter().find(|a| a.name == interface))
+}
+
+/// create a veth pair and bring both ends up
+pub fn create_veth(name: &str, peer: &str) -> Result<()> {
+    with_handle(|handle| async move {
+        handle
+            .link()
+            .add(LinkVeth::new(name, peer).up().build())
+            .execute()
+            .await
+            .with_context(|| format!("Failed to create veth pair {}/{}", name, peer))?;
+
+        handle
+            .link()
+            .set(LinkUnspec::new_with_name(peer).up().build())
+            .execute()
+            .await
+            .with_context(|| format!("Failed to bring up {}", peer))?;
+
+        Ok(())
+    })
+}
+
+/// delete a link by name, removing a veth deletes its peer as well
+pub fn delete_link(name: &str) -> Result<()> {
+    with_handle(|handle| async move {
+        let msg = handle
+            .link()
+            .get()
+            .match_name(name.to_string())
+            .execute()
+            .try_next()
+            .await
+            .with_context(|| format!("Failed to look up {}", name))?
+            .ok_or_else(|| anyhow::anyhow!("No such interface: {}", name))?;
+
+        handle
+            .link()
+            .del(msg.header.index)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to delete {}", name))
+    })
+}
+
+/// send a single raw request on a `protocol` socket and collect the payload of
+/// every reply, for families the rtnetlink crate does not cover
+pub fn request(protocol: isize, msg_type: u16, flags: u16, payload: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut socket = Socket::new(protocol).context("Failed to open netlink socket")?;
+    socket.bind_auto().context("Failed to bind netlink socket")?;
+    socket.connect(&SocketAddr::new(0, 0))?;
+
+    let len = NLMSG_HDRLEN + payload.len();
+    let mut msg = Vec::with_capacity(len);
+    msg.extend_from_slice(&(len as u32).to_ne_bytes());
+    msg.extend_from_slice(&msg_type.to_ne_bytes());
+    msg.extend_from_slice(&(flags | NLM_F_REQUEST).to_ne_bytes());
+    msg.extend_from_slice(&1u32.to_ne_bytes()); // seq
+    msg.extend_from_slice(&0u32.to_ne_bytes()); // port id, kernel fills it in
+    msg.extend_from_slice(payload);
+
+    socket.send(&msg, 0).context("Failed to send netlink request")?;
+
+    let mut replies = Vec::new();
+    loop {
+        let (buf, _) = socket.recv_from_full().context("Failed to read netlink reply")?;
+        let mut offset = 0;
+
+        while offset + NLMSG_HDRLEN <= buf.len() {
+            let len = ne_u32(&buf, offset) as usize;
+            let kind = u16::from_ne_bytes([buf[offset + 4], buf[offset + 5]]);
+            if len < NLMSG_HDRLEN || offset + len > buf.len() {
+                anyhow::bail!("Truncated netlink message");
+            }
+            let body = &buf[offset + NLMSG_HDRLEN..offset + len];
+
+            match kind {
+                NLMSG_DONE => return Ok(replies),
+                NLMSG_ERROR => {
+                    let code = ne_u32(body, 0) as i32;
+                    if code == 0 {
+                        // plain ack for a non-dump request
+                        return Ok(replies);
+                    }
+                    return Err(io::Error::from_raw_os_error(-code).into());
+                }
+                _ => replies.push(body.to_vec()),
+            }
+
+            if flags & NLM_F_DUMP == 0 {
+                return Ok(replies);
+            }
+
+            offset += (len + 3) & !3;
+        }
+    }
+}
+
+/// (type, value) of every attribute in `buf`, nested/byteorder flags masked off
+pub fn attributes(buf: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attrs = Vec::new();
+    let mut offset = 0;
+
+    while offset + 4 <= buf.len() {
+        let len = u16::from_ne_bytes([buf[offset], buf[offset + 1]]) as usize;
+        let kind = u16::from_ne_bytes([buf[offset + 2], buf[offset + 3]]) & 0x3fff;
+        if len < 4 || offset + len > buf.len() {
+            break;
+        }
+        attrs.push((kind, &buf[offset + 4..offset + len]));
+        offset += (len + 3) & !3;
+    }
+
+    attrs
+}
+
+/// native-endian field readers for kernel structs, 0 when out of bounds
+pub fn ne_u32(buf: &[u8], offset: usize) -> u32 {
+    buf.get(offset..offset + 4)
+        .map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+pub fn ne_u64(buf: &[u8], offset: usize) -> u64 {
+    buf.get(offset..offset + 8)
+        .map(|b| u64::from_ne_bytes(b.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+/// append a netlink attribute, padded to 4 bytes
+pub fn put_attr(buf: &mut Vec<u8>, kind: u16, value: &[u8]) {
+    buf.extend_from_slice(&((4 + value.len()) as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(value);
+    buf.resize((buf.len() + 3) & !3, 0);
+}
+
+/// resolve a generic netlink family name to its id
+pub fn genl_family_id(name: &str) -> Result<u16> {
+    let mut attrs = Vec::new();
+    let mut family = name.as_bytes().to_vec();
+    family.push(0);
+    put_attr(&mut attrs, CTRL_ATTR_FAMILY_NAME, &family);
+
+    let replies = genl_request(GENL_ID_CTRL, CTRL_CMD_GETFAMILY, 1, 0, &attrs)
+        .with_context(|| format!("Generic netlink family '{}' not available", name))?;
+
+    let (_, value) = replies
+        .iter()
+        .flat_map(|reply| attributes(reply))
+        .find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)
+        .ok_or_else(|| anyhow::anyhow!("No id returned for family '{}'", name))?;
+
+    value
+        .get(..2)
+        .map(|id| u16::from_ne_bytes([id[0], id[1]]))
+        .ok_or_else(|| anyhow::anyhow!("Short family id for '{}': {} byte(s)", name, value.len()))
+}
+
+/// generic netlink request, returns the attributes of every reply
+pub fn genl_request(family: u16, cmd: u8, version: u8, flags: u16, attrs: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut payload = vec![cmd, version, 0, 0];
+    payload.extend_from_slice(attrs);
+
+    Ok(request(NETLINK_GENERIC, family, flags, &payload)?
+        .into_iter()
+        .filter(|reply| reply.len() >= GENL_HDRLEN)
+        .map(|reply| reply[GENL_HDRLEN..].to_vec())
+        .collect())
+}
+
+/// NETDEV_CMD_DEV_GET, fails on kernels without the netdev family
+pub fn xdp_features(ifindex: u32) -> Result<XdpFeatures> {
+    let family = genl_family_id("netdev")?;
+
+    let mut attrs = Vec::new();
+    put_attr(&mut attrs, NETDEV_A_DEV_IFINDEX, &ifindex.to_ne_bytes());
+
+    let replies = genl_request(family, NETDEV_CMD_DEV_GET, 1, 0, &attrs)?;
+
+    let mut features = XdpFeatures {
+        flags: 0,
+        zc_max_segs: None,
+    };
+    for reply in &replies {
+        for (kind, value) in attributes(reply) {
+            match kind {
+                NETDEV_A_DEV_XDP_FEATURES => features.flags = ne_u64(value, 0),
+                NETDEV_A_DEV_XDP_ZC_MAX_SEGS => features.zc_max_segs = Some(ne_u32(value, 0)),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(features)
+}
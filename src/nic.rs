@@ -1,53 +1,126 @@
 use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::Path;
-use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
-use libc::{ifreq, socket, ioctl, AF_INET, SOCK_DGRAM, SIOCETHTOOL, IF_NAMESIZE};
-use std::{mem, ptr};
+use nix::net::if_::if_nametoindex;
 
+use crate::drivers;
+use crate::ethtool::{self, Channels};
+use crate::kernel::KernelVersion;
+use crate::netlink::{self, XdpFeatures};
 use crate::output::{CheckResult, CheckStatus};
+use crate::system;
+use crate::xsk;
 
-/// Known good drivers with XDP support
-const GOOD_DRIVERS: &[&str] = &[
-    "i40e",     // intel 40GbE (has bugs but widely used)
-    "ixgbe",    // intel 10GbE
-    "ice",      // intel E810 100GbE
-    "igb",      // intel 1GbE
-    "igc",      // intel 2.5GbE
-    "mlx5_core", // mellanox ConnectX-4/5/6
-    "mlx4_core", // mellanox ConnectX-3
-    "nfp",      // netronome
-    "bnxt_en",  // broadcom NetXtreme
-    // "ena",      // amazon ENA
-    // "veth",     // virtual ethernet (for testing)
-    // "tun",      // TUN/TAP (limited support)
-    // "virtio_net", // virtio network (with caveats)
+/// RXH_* bits of the GRXFH hash field set
+const RXH_FIELDS: &[(u64, &str)] = &[
+    (1 << 1, "dst MAC"),
+    (1 << 2, "VLAN"),
+    (1 << 3, "L3 proto"),
+    (1 << 4, "src IP"),
+    (1 << 5, "dst IP"),
+    (1 << 6, "src port"),
+    (1 << 7, "dst port"),
 ];
 
-/// Drivers with known issues
-const PROBLEMATIC_DRIVERS: &[(&str, &str)] = &[
-    ("i40e", "multi-fragment packet bugs - requires workaround in slowgave XDP"),
-    // ("virtio_net", "limited XDP support, no zero-copy"),
-    // ("tun", "generic XDP only, poor performance"),
-];
+/// ETH_RSS_HASH_* bits of ethtool_rxfh.hfunc
+const RSS_HASH_FUNCS: &[(u8, &str)] = &[(1 << 0, "toeplitz"), (1 << 1, "xor"), (1 << 2, "crc32")];
+
+/// RX descriptors below which a ring is likely to overflow on bursts at validator packet rates
+pub const DEFAULT_MIN_RX_RING: u32 = 1024;
 
-/// ring buffer parameters from ethtool
-#[repr(C)]
-struct EthtoolRingParam {
-    cmd: u32,
-    rx_max_pending: u32,
-    rx_mini_max_pending: u32,
-    rx_jumbo_max_pending: u32,
-    tx_max_pending: u32,
-    rx_pending: u32,
-    rx_mini_pending: u32,
-    rx_jumbo_pending: u32,
-    tx_pending: u32,
+/// a netdev feature state that stops native XDP from attaching or changes what
+/// the program sees
+struct OffloadConflict {
+    /// kernel feature name, as ethtool -k lists it
+    feature: &'static str,
+    /// ethtool -K arguments that resolve it
+    fix: &'static str,
+    conflicts_when_on: bool,
+    /// attach fails (Warning, Fail when the driver pins the feature) rather than
+    /// degrading the program's view of the packet (Info)
+    blocks_attach: bool,
+    problem: &'static str,
 }
 
-const ETHTOOL_GRINGPARAM: u32 = 0x00000010;
+const OFFLOAD_CONFLICTS: &[OffloadConflict] = &[
+    OffloadConflict {
+        feature: "rx-lro",
+        fix: "lro off",
+        conflicts_when_on: true,
+        blocks_attach: true,
+        problem: "native XDP attach fails on bnxt_en, mlx5, qede and others while LRO merges frames",
+    },
+    OffloadConflict {
+        feature: "rx-gro-hw",
+        fix: "rx-gro-hw off",
+        conflicts_when_on: true,
+        blocks_attach: true,
+        problem: "hardware GRO hands XDP merged multi-page frames, most drivers (bnxt_en, mlx5, virtio_net) refuse to attach",
+    },
+    OffloadConflict {
+        feature: "rx-vlan-hw-parse",
+        fix: "rxvlan off",
+        conflicts_when_on: true,
+        blocks_attach: false,
+        problem: "VLAN tags are stripped before XDP runs, the program sees untagged frames",
+    },
+    OffloadConflict {
+        feature: "rx-hashing",
+        fix: "rxhash on",
+        conflicts_when_on: false,
+        blocks_attach: false,
+        problem: "the RSS hash is not passed up, bpf_xdp_metadata_rx_hash and AF_XDP consumers get none",
+    },
+];
 
-pub fn check_all_interfaces() -> Result<Vec<CheckResult>> {
+/// ETHTOOL_TCP_DATA_SPLIT_* values
+const TCP_DATA_SPLIT: &[(u8, &str)] = &[(0, "unknown"), (1, "off"), (2, "on")];
+
+/// how a driver prefixes per-queue counters, "{}" is the queue number.
+/// the label says which ring the counter belongs to.
+const QUEUE_PREFIXES: &[(&str, &[(&str, &str)])] = &[
+    ("mlx5_core", &[("rx{}_", "rx"), ("tx{}_", "tx"), ("ch{}_", "ch"), ("xskrq{}_", "xsk_rx"), ("xsksq{}_", "xsk_tx")]),
+    ("ice", &[("rx_queue_{}_", "rx"), ("tx_queue_{}_", "tx"), ("xdp_queue_{}_", "xdp_tx")]),
+    ("i40e", &[("rx-{}.", "rx"), ("tx-{}.", "tx"), ("xdp-{}.", "xdp_tx")]),
+    ("ixgbe", &[("rx_queue_{}_", "rx"), ("tx_queue_{}_", "tx"), ("xdp_queue_{}_", "xdp_tx")]),
+    // veth, virtio_net and most others
+    ("", &[("rx_queue_{}_", "rx"), ("tx_queue_{}_", "tx"), ("rx{}_", "rx"), ("tx{}_", "tx")]),
+];
+
+/// driver counter names (after the queue prefix, joined to the ring label)
+/// mapped to one vocabulary: xdp_drop, xdp_redirect, xdp_tx, xdp_xmit
+const STAT_ALIASES: &[(&str, &str, &str)] = &[
+    ("mlx5_core", "rx_xdp_drop", "xdp_drop"),
+    ("mlx5_core", "rx_xdp_redirect", "xdp_redirect"),
+    ("mlx5_core", "rx_xdp_tx_xmit", "xdp_tx"),
+    ("mlx5_core", "rx_xdp_tx_full", "xdp_tx_full"),
+    ("mlx5_core", "rx_xdp_tx_err", "xdp_tx_err"),
+    ("mlx5_core", "tx_xdp_xmit", "xdp_xmit"),
+    ("mlx5_core", "tx_xdp_full", "xdp_xmit_full"),
+    ("mlx5_core", "tx_xdp_err", "xdp_xmit_err"),
+    ("mlx5_core", "xsk_rx_packets", "xsk_rx_packets"),
+    ("mlx5_core", "xsk_tx_xmit", "xsk_tx_packets"),
+    ("ice", "xdp_tx_packets", "xdp_tx"),
+    ("i40e", "xdp_tx_packets", "xdp_tx"),
+    ("ixgbe", "xdp_tx_packets", "xdp_tx"),
+    ("", "rx_xdp_drops", "xdp_drop"),
+    ("", "rx_xdp_redirect", "xdp_redirect"),
+    ("", "rx_xdp_tx", "xdp_tx"),
+    ("", "rx_xdp_tx_errors", "xdp_tx_err"),
+    ("", "tx_xdp_xmit", "xdp_xmit"),
+    ("", "tx_xdp_xmit_errors", "xdp_xmit_err"),
+];
+
+/// one XDP or XSK counter from ethtool -S, with the name normalized
+pub struct XdpStat {
+    /// None for device-wide counters
+    pub queue: Option<u32>,
+    pub name: String,
+    pub value: u64,
+}
+
+pub fn check_all_interfaces(profile: Option<system::TuningProfile>) -> Result<Vec<CheckResult>> {
     let mut results = Vec::new();
 
     // list of network interfaces
@@ -68,15 +141,15 @@ pub fn check_all_interfaces() -> Result<Vec<CheckResult>> {
             continue; // skip loopback
         }
 
-        let iface_results = check_interface_internal(&iface)?;
+        let iface_results = check_interface_internal(&iface, DEFAULT_MIN_RX_RING, profile)?;
         results.extend(iface_results);
     }
 
     Ok(results)
 }
 
-pub fn check_interface(interface: &str) -> Result<Vec<CheckResult>> {
-    check_interface_internal(interface)
+pub fn check_interface(interface: &str, min_rx_ring: u32, profile: Option<system::TuningProfile>) -> Result<Vec<CheckResult>> {
+    check_interface_internal(interface, min_rx_ring, profile)
 }
 
 pub fn quick_interface_check() -> Result<Vec<CheckResult>> {
@@ -92,7 +165,7 @@ pub fn quick_interface_check() -> Result<Vec<CheckResult>> {
         }
 
         if let Ok(driver) = interface_driver(&iface) {
-            if GOOD_DRIVERS.contains(&driver.as_str()) {
+            if xdp_capability(&iface, &driver).native() {
                 xdp_capable.push(format!("{} ({})", iface, driver));
             } else {
                 non_xdp.push(format!("{} ({})", iface, driver));
@@ -124,7 +197,7 @@ pub fn quick_interface_check() -> Result<Vec<CheckResult>> {
     Ok(results)
 }
 
-fn check_interface_internal(interface: &str) -> Result<Vec<CheckResult>> {
+fn check_interface_internal(interface: &str, min_rx_ring: u32, profile: Option<system::TuningProfile>) -> Result<Vec<CheckResult>> {
     let mut results = Vec::new();
 
     let sys_path = format!("/sys/class/net/{}", interface);
@@ -151,24 +224,45 @@ fn check_interface_internal(interface: &str) -> Result<Vec<CheckResult>> {
     });
 
     let driver = interface_driver(interface)?;
+    let capability = xdp_capability(interface, &driver);
+    let drvinfo = ethtool::driver_info(interface).ok();
+    let driver_version = drvinfo.as_ref().and_then(|d| d.version.as_deref());
+    let running_kernel = KernelVersion::running().ok();
 
-    let driver_status = if GOOD_DRIVERS.contains(&driver.as_str()) {
-        if driver == "i40e" {
-            CheckStatus::Warning
-        } else {
-            CheckStatus::Pass
-        }
+    let driver_info = drivers::db().get(&driver);
+    let known_bugs = driver_info.map(|d| d.known_bugs.as_slice()).unwrap_or_default();
+    let (active_bugs, inactive_bugs): (Vec<_>, Vec<_>) = known_bugs
+        .iter()
+        .partition(|bug| bug.applies(running_kernel.as_ref(), driver_version));
+
+    let driver_status = if capability.native() && active_bugs.is_empty() {
+        CheckStatus::Pass
     } else {
         CheckStatus::Warning
     };
 
-    let mut driver_details = format!("Driver: {}", driver);
-
-    for (problematic_driver, issue) in PROBLEMATIC_DRIVERS {
-        if driver == *problematic_driver {
-            driver_details = format!("Driver: {} - KNOWN ISSUE: {}", driver, issue);
-            break;
-        }
+    let mut driver_details = format!(
+        "Driver: {}\nNative XDP: {} (from {})",
+        driver,
+        if capability.native() { "yes" } else { "no" },
+        capability.source()
+    );
+    if let Some(drvinfo) = &drvinfo {
+        driver_details.push_str(&format!(
+            "\nDriver version: {}\nFirmware: {}",
+            drvinfo.version.as_deref().unwrap_or("unknown"),
+            drvinfo.firmware.as_deref().unwrap_or("unknown")
+        ));
+    }
+    match driver_info {
+        Some(info) => driver_details.push_str(&format!("\n{}", info.details())),
+        None => driver_details.push_str("\nNot in the driver database"),
+    }
+    for bug in active_bugs {
+        driver_details.push_str(&format!("\nKNOWN ISSUE: {}", bug.describe()));
+    }
+    for bug in inactive_bugs {
+        driver_details.push_str(&format!("\nNot affected: {}", bug.describe()));
     }
 
     results.push(CheckResult {
@@ -178,84 +272,281 @@ fn check_interface_internal(interface: &str) -> Result<Vec<CheckResult>> {
         details: Some(driver_details),
     });
 
-    let xdp_status = check_xdp_support(interface)?;
-    results.push(xdp_status);
+    if let XdpCapability::Features(features) = &capability {
+        let supported = features.supported();
+        results.push(CheckResult {
+            name: format!("{}: XDP Features", interface),
+            status: if capability.native() { CheckStatus::Pass } else { CheckStatus::Warning },
+            message: if supported.is_empty() {
+                "Driver advertises no XDP features (generic XDP only)".to_string()
+            } else {
+                format!("Driver advertises: {}", supported.join(", "))
+            },
+            details: Some(features.details()),
+        });
+    }
 
-    let queues = interface_queues(interface)?;
-    results.push(CheckResult {
-        name: format!("{}: Queues", interface),
-        status: CheckStatus::Info,
-        message: format!("RX queues: {}, TX queues: {}", queues.0, queues.1),
-        details: Some("Multiple queues enable multi-core XDP processing".to_string()),
-    });
+    let xdp_status = check_xdp_support(interface, &driver, &capability)?;
+    results.push(xdp_status);
 
-    // check ring buffer sizes using ethtool ioctl
-    match ring_parameters_ethtool(interface) {
-        Ok((rx, tx)) => {
+    let rx_channels = match ethtool::channels(interface) {
+        Ok(channels) => {
+            results.push(channels_result(interface, &channels));
+            Some(channels.combined_count + channels.rx_count)
+        }
+        Err(e) => {
+            log::debug!("{}: ETHTOOL_GCHANNELS failed: {:#}", interface, e);
+            let (rx, tx) = interface_queues(interface);
             results.push(CheckResult {
-                name: format!("{}: Ring Buffers", interface),
+                name: format!("{}: Queues", interface),
                 status: CheckStatus::Info,
-                message: format!("RX: {}, TX: {}", rx, tx),
-                details: Some("Ring buffer size affects XDP performance and memory usage".to_string()),
+                message: format!("RX queues: {}, TX queues: {}", rx, tx),
+                details: Some("From /sys/class/net, the driver does not report channels".to_string()),
             });
+            None
         }
-        Err(_) => {
-            // if ethtool fails, check sysfs as fallback
-            results.push(CheckResult {
-                name: format!("{}: Ring Buffers", interface),
-                status: CheckStatus::Warning,
-                message: "Unable to determine ring buffer sizes".to_string(),
-                details: Some("Could not query ring parameters via ethtool".to_string()),
-            });
+    };
+
+    results.extend(check_rss(interface, rx_channels));
+
+    results.extend(check_rings(interface, min_rx_ring));
+
+    results.extend(check_xdp_stats(interface, &driver));
+
+    // link speed, sysfs when the driver has no link settings
+    match ethtool::link(interface) {
+        Ok(link) => results.push(link_result(interface, &link, ethtool::pause(interface).ok())),
+        Err(e) => {
+            log::debug!("{}: ethtool link settings failed: {:#}", interface, e);
+            if let Ok(speed) = fs::read_to_string(format!("{}/speed", sys_path)) {
+                if let Ok(speed_mbps) = speed.trim().parse::<u32>() {
+                    results.push(CheckResult {
+                        name: format!("{}: Speed", interface),
+                        status: CheckStatus::Info,
+                        message: format_speed(speed_mbps),
+                        details: None,
+                    });
+                }
+            }
         }
     }
 
-    // check interface speed
-    if let Ok(speed) = fs::read_to_string(format!("{}/speed", sys_path)) {
-        let speed = speed.trim();
-        if let Ok(speed_mbps) = speed.parse::<u32>() {
-            results.push(CheckResult {
-                name: format!("{}: Speed", interface),
-                status: CheckStatus::Info,
-                message: if speed_mbps >= 10000 {
-                    format!("{} Gbps", speed_mbps / 1000)
-                } else {
-                    format!("{} Mbps", speed_mbps)
-                },
-                details: None,
-            });
-        }
+    results.extend(check_offloads(interface));
+
+    results.extend(system::check_interface_tuning(interface, profile));
+
+    if let Some(mtu) = fs::read_to_string(format!("{}/mtu", sys_path))
+        .ok()
+        .and_then(|mtu| mtu.trim().parse::<u32>().ok())
+    {
+        results.push(check_mtu(interface, mtu, &driver, driver_info, &capability, running_kernel.as_ref()));
     }
 
-    // MTU
-    if let Ok(mtu) = fs::read_to_string(format!("{}/mtu", sys_path)) {
-        let mtu = mtu.trim();
-        results.push(CheckResult {
-            name: format!("{}: MTU", interface),
+    Ok(results)
+}
+
+/// whether the MTU fits one page per frame, and multi-buffer support when it does not
+fn check_mtu(
+    interface: &str,
+    mtu: u32,
+    driver: &str,
+    driver_info: Option<&drivers::DriverInfo>,
+    capability: &XdpCapability,
+    kernel: Option<&KernelVersion>,
+) -> CheckResult {
+    let name = format!("{}: MTU", interface);
+
+    let Some(limit) = driver_info.and_then(|d| d.xdp_max_mtu) else {
+        return CheckResult {
+            name,
             status: CheckStatus::Info,
             message: format!("MTU: {} bytes", mtu),
+            details: Some(format!("No single-buffer XDP MTU limit known for {}", driver)),
+        };
+    };
+
+    if mtu <= limit {
+        return CheckResult {
+            name,
+            status: CheckStatus::Pass,
+            message: format!("MTU {} fits a single XDP buffer (limit {})", mtu, limit),
             details: None,
+        };
+    }
+
+    let frags = match capability {
+        XdpCapability::Features(features) => features.has(netlink::NETDEV_XDP_ACT_RX_SG),
+        XdpCapability::DriverTable(_) => driver_info.is_some_and(|d| d.supports_multi_buffer(kernel)),
+    };
+    let source = capability.source();
+    let lower = format!("ip link set dev {} mtu {}", interface, limit);
+
+    if frags {
+        CheckResult {
+            name,
+            status: CheckStatus::Warning,
+            message: format!("MTU {} exceeds the single-buffer XDP limit {}, needs multi-buffer", mtu, limit),
+            details: Some(format!(
+                "{} supports XDP multi-buffer (from {})\nThe program must be frags-aware (SEC(\"xdp.frags\"), BPF_F_XDP_HAS_FRAGS) or native attach fails\nOr lower the MTU: {}",
+                driver, source, lower
+            )),
+        }
+    } else {
+        let multi_buffer = match driver_info.and_then(|d| d.multi_buffer.as_deref()) {
+            Some(version) => format!("{} has multi-buffer from kernel {}", driver, version),
+            None => format!("{} has no XDP multi-buffer support", driver),
+        };
+        CheckResult {
+            name,
+            status: CheckStatus::Fail,
+            message: format!(
+                "MTU {} exceeds the single-buffer XDP limit {} and multi-buffer is unavailable",
+                mtu, limit
+            ),
+            details: Some(format!(
+                "Native XDP will not attach, only generic mode\n{} (from {})\nLower the MTU: {}",
+                multi_buffer, source, lower
+            )),
+        }
+    }
+}
+
+fn format_speed(mbps: u32) -> String {
+    if mbps >= 10000 {
+        format!("{} Gbps", mbps / 1000)
+    } else {
+        format!("{} Mbps", mbps)
+    }
+}
+
+fn link_result(interface: &str, link: &ethtool::LinkSettings, pause: Option<ethtool::Pause>) -> CheckResult {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+
+    let mut details = vec![format!("Autonegotiation: {}", on_off(link.autoneg))];
+    if let Some(port) = link.port_name() {
+        details.push(format!("Port: {}", port));
+    }
+    if let Some(lanes) = link.lanes {
+        details.push(format!("Lanes: {}", lanes));
+    }
+    if !link.advertised.is_empty() {
+        details.push(format!("Advertised: {}", link.advertised.join(", ")));
+    }
+    if !link.peer.is_empty() {
+        details.push(format!("Link partner: {}", link.peer.join(", ")));
+    }
+    if let Some(pause) = pause {
+        details.push(format!(
+            "Pause: autoneg {}, rx {}, tx {}",
+            on_off(pause.autoneg),
+            on_off(pause.rx),
+            on_off(pause.tx)
+        ));
+    }
+
+    CheckResult {
+        name: format!("{}: Speed", interface),
+        status: CheckStatus::Info,
+        message: match (link.speed, link.full_duplex) {
+            (Some(speed), Some(full)) => {
+                format!("{}, {} duplex", format_speed(speed), if full { "full" } else { "half" })
+            }
+            (Some(speed), None) => format_speed(speed),
+            (None, _) => "Speed unknown (link down?)".to_string(),
+        },
+        details: Some(details.join("\n")),
+    }
+}
+
+/// active netdev features (ethtool -k), and every offload that conflicts with XDP
+fn check_offloads(interface: &str) -> Vec<CheckResult> {
+    let features = match ethtool::features(interface) {
+        Ok(features) => features,
+        Err(e) => {
+            log::debug!("{}: ethtool features failed: {:#}", interface, e);
+            return Vec::new();
+        }
+    };
+
+    let join = |names: &BTreeSet<String>| {
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.iter().cloned().collect::<Vec<_>>().join(", ")
+        }
+    };
+    let mut results = Vec::new();
+    let mut fixes = Vec::new();
+
+    for conflict in OFFLOAD_CONFLICTS {
+        let active = features.active.contains(conflict.feature);
+        let changeable = features.changeable(conflict.feature);
+        // a feature that is off only conflicts when the driver could turn it on
+        let conflicts = if conflict.conflicts_when_on { active } else { !active && changeable };
+        if !conflicts {
+            continue;
+        }
+
+        let fix = format!("ethtool -K {} {}", interface, conflict.fix);
+        results.push(CheckResult {
+            name: format!("{}: Offload {}", interface, conflict.feature),
+            status: match (conflict.blocks_attach, changeable) {
+                (true, false) => CheckStatus::Fail,
+                (true, true) => CheckStatus::Warning,
+                (false, _) => CheckStatus::Info,
+            },
+            message: format!(
+                "{} is {}: {}",
+                conflict.feature,
+                if conflict.conflicts_when_on { "on" } else { "off" },
+                conflict.problem
+            ),
+            details: Some(if changeable {
+                fixes.push(conflict.fix);
+                format!("Fix: {}", fix)
+            } else {
+                format!("The driver does not allow changing {}", conflict.feature)
+            }),
         });
     }
 
-    Ok(results)
+    let pinned: BTreeSet<String> = features.active.iter().filter(|f| !features.changeable(f)).cloned().collect();
+    let mut details = format!("Active: {}\nNot changeable: {}", join(&features.active), join(&pinned));
+    if !fixes.is_empty() {
+        details.push_str(&format!("\nFix all: ethtool -K {} {}", interface, fixes.join(" ")));
+    }
+
+    results.insert(
+        0,
+        CheckResult {
+            name: format!("{}: Offloads", interface),
+            status: CheckStatus::Info,
+            message: format!(
+                "{} features active, {} changeable, {} conflicting with XDP",
+                features.active.len(),
+                features.hw.iter().filter(|f| features.changeable(f)).count(),
+                results.len()
+            ),
+            details: Some(details),
+        },
+    );
+
+    results
 }
 
-fn network_interfaces() -> Result<Vec<String>> {
+pub fn network_interfaces() -> Result<Vec<String>> {
     let mut interfaces = Vec::new();
 
-    for entry in fs::read_dir("/sys/class/net")? {
-        if let Ok(entry) = entry {
-            if let Some(name) = entry.file_name().to_str() {
-                interfaces.push(name.to_string());
-            }
+    for entry in fs::read_dir("/sys/class/net")?.flatten() {
+        if let Some(name) = entry.file_name().to_str() {
+            interfaces.push(name.to_string());
         }
     }
 
     Ok(interfaces)
 }
 
-fn interface_driver(interface: &str) -> Result<String> {
+pub fn interface_driver(interface: &str) -> Result<String> {
     let driver_path = format!("/sys/class/net/{}/device/driver", interface);
 
     if let Ok(link) = fs::read_link(&driver_path) {
@@ -266,6 +557,11 @@ fn interface_driver(interface: &str) -> Result<String> {
         }
     }
 
+    // virtual interfaces have no device link but still answer GDRVINFO
+    if let Ok(drvinfo) = ethtool::driver_info(interface) {
+        return Ok(drvinfo.driver);
+    }
+
     // fallback for virtual interfaces
     let uevent_path = format!("/sys/class/net/{}/uevent", interface);
     if let Ok(content) = fs::read_to_string(&uevent_path) {
@@ -279,113 +575,576 @@ fn interface_driver(interface: &str) -> Result<String> {
     Ok("unknown".to_string())
 }
 
-fn check_xdp_support(interface: &str) -> Result<CheckResult> {
-    let xdp_path = format!("/sys/class/net/{}/xdp", interface);
-    let xdp_prog_path = format!("{}/prog_id", xdp_path);
-
-    if !Path::new(&xdp_path).exists() {
-        // nothing found
-        // check if the driver is in the known good list
-        let driver = interface_driver(interface).unwrap_or_else(|_| "unknown".to_string());
-        if GOOD_DRIVERS.contains(&driver.as_str()) {
-            return Ok(CheckResult {
-                name: format!("{}: XDP Support", interface),
-                status: CheckStatus::Pass,
-                message: "XDP-capable (driver supports native XDP)".to_string(),
-                details: Some(format!("Driver {} supports XDP but sysfs entries not visible", driver)),
-            });
+/// where the native XDP verdict for an interface comes from
+enum XdpCapability {
+    /// xdp-features from the netdev genetlink family (6.3+)
+    Features(XdpFeatures),
+    /// older kernels, driver database lookup
+    DriverTable(bool),
+}
+
+impl XdpCapability {
+    fn native(&self) -> bool {
+        match self {
+            XdpCapability::Features(features) => features.has(netlink::NETDEV_XDP_ACT_BASIC),
+            XdpCapability::DriverTable(known) => *known,
+        }
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            XdpCapability::Features(_) => "netdev xdp-features",
+            XdpCapability::DriverTable(_) => "driver database",
         }
+    }
+}
+
+fn xdp_capability(interface: &str, driver: &str) -> XdpCapability {
+    let features = if_nametoindex(interface)
+        .map_err(anyhow::Error::from)
+        .and_then(netlink::xdp_features);
+
+    match features {
+        Ok(features) => XdpCapability::Features(features),
+        Err(e) => {
+            log::debug!("netdev xdp-features unavailable for {}: {:#}", interface, e);
+            let native = drivers::db()
+                .get(driver)
+                .is_some_and(|info| info.supports_native(KernelVersion::running().ok().as_ref()));
+            XdpCapability::DriverTable(native)
+        }
+    }
+}
+
+fn check_xdp_support(interface: &str, driver: &str, capability: &XdpCapability) -> Result<CheckResult> {
+    let attachment = match netlink::xdp_attachment(interface) {
+        Ok(attachment) => attachment,
+        Err(e) => {
+            log::debug!("RTM_GETLINK failed for {}: {:#}", interface, e);
+            None
+        }
+    };
+
+    if let Some(attachment) = attachment.as_ref().filter(|a| a.is_attached()) {
+        let programs: Vec<String> = attachment
+            .programs()
+            .iter()
+            .map(|(mode, id)| format!("{} (ID: {})", mode.as_str(), id))
+            .collect();
 
         return Ok(CheckResult {
             name: format!("{}: XDP Support", interface),
-            status: CheckStatus::Warning,
-            message: "No XDP support detected".to_string(),
-            details: Some("XDP sysfs entries not found. Driver may not support native XDP.".to_string()),
+            status: CheckStatus::Pass,
+            message: format!("XDP program attached: {}", programs.join(", ")),
+            details: Some(attachment.details()),
         });
     }
 
-    // is XDP program is attached
-    if let Ok(prog_id) = fs::read_to_string(&xdp_prog_path) {
-        let prog_id = prog_id.trim();
-        if prog_id != "0" && !prog_id.is_empty() {
-            return Ok(CheckResult {
-                name: format!("{}: XDP Support", interface),
-                status: CheckStatus::Pass,
-                message: format!("XDP program attached (ID: {})", prog_id),
-                details: Some("Interface has an active XDP program".to_string()),
-            });
-        }
+    // nothing attached, so IFLA_XDP says nothing about driver support
+    let source = if attachment.is_some() {
+        "no program attached (rtnetlink)"
+    } else {
+        "IFLA_XDP not available"
+    };
+
+    if capability.native() {
+        return Ok(CheckResult {
+            name: format!("{}: XDP Support", interface),
+            status: CheckStatus::Pass,
+            message: "XDP-ready (no program attached)".to_string(),
+            details: Some(format!(
+                "Driver {} supports native XDP ({}), {}",
+                driver,
+                capability.source(),
+                source
+            )),
+        });
     }
 
     Ok(CheckResult {
         name: format!("{}: XDP Support", interface),
-        status: CheckStatus::Pass,
-        message: "XDP-ready (no program attached)".to_string(),
-        details: Some("Interface supports XDP but no program is currently attached".to_string()),
+        status: CheckStatus::Warning,
+        message: "No native XDP support detected".to_string(),
+        details: Some(format!(
+            "Driver {} does not support native XDP ({}), {}. Generic XDP still works.",
+            driver,
+            capability.source(),
+            source
+        )),
     })
 }
 
-/// get number of RX and TX queues
-fn interface_queues(interface: &str) -> Result<(usize, usize)> {
+/// RX and TX queue directories in sysfs
+fn interface_queues(interface: &str) -> (usize, usize) {
     let queue_path = format!("/sys/class/net/{}/queues", interface);
 
     let mut rx_queues = 0;
     let mut tx_queues = 0;
 
     if let Ok(entries) = fs::read_dir(&queue_path) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if let Some(name) = entry.file_name().to_str() {
-                    if name.starts_with("rx-") {
-                        rx_queues += 1;
-                    } else if name.starts_with("tx-") {
-                        tx_queues += 1;
-                    }
+        for entry in entries.flatten() {
+            if let Some(name) = entry.file_name().to_str() {
+                if name.starts_with("rx-") {
+                    rx_queues += 1;
+                } else if name.starts_with("tx-") {
+                    tx_queues += 1;
                 }
             }
         }
     }
 
-    // if no queues found in sysfs, assume at least 1. need to check
-    if rx_queues == 0 {
-        rx_queues = 1;
+    (rx_queues, tx_queues)
+}
+
+fn channels_result(interface: &str, channels: &Channels) -> CheckResult {
+    let rx_channels = channels.combined_count + channels.rx_count;
+    let isolated = system::isolated_cpus();
+
+    let mut details = format!(
+        "Combined: {} (max {})\nRX: {} (max {})\nTX: {} (max {})\nOther: {} (max {})",
+        channels.combined_count,
+        channels.max_combined,
+        channels.rx_count,
+        channels.max_rx,
+        channels.tx_count,
+        channels.max_tx,
+        channels.other_count,
+        channels.max_other
+    );
+
+    let too_many = !isolated.is_empty() && rx_channels as usize > isolated.len();
+    if isolated.is_empty() {
+        details.push_str("\nNo isolated CPUs (isolcpus=), queue IRQs share CPUs with everything else");
+    } else {
+        details.push_str(&format!("\nIsolated CPUs: {}", isolated.len()));
     }
-    if tx_queues == 0 {
-        tx_queues = 1;
+    if too_many {
+        details.push_str(&format!(
+            "\nQueues beyond the isolated CPUs put IRQs on housekeeping CPUs. Reduce with: ethtool -L {} combined {}",
+            interface,
+            isolated.len()
+        ));
     }
 
-    Ok((rx_queues, tx_queues))
+    CheckResult {
+        name: format!("{}: Channels", interface),
+        status: if too_many { CheckStatus::Warning } else { CheckStatus::Info },
+        message: if too_many {
+            format!("{} RX channels but only {} isolated CPUs", rx_channels, isolated.len())
+        } else {
+            format!(
+                "combined {}/{}, rx {}/{}, tx {}/{}, other {}/{} (current/max)",
+                channels.combined_count,
+                channels.max_combined,
+                channels.rx_count,
+                channels.max_rx,
+                channels.tx_count,
+                channels.max_tx,
+                channels.other_count,
+                channels.max_other
+            )
+        },
+        details: Some(details),
+    }
 }
 
-/// ring buffer parameters using ethtool ioctl (alessandros device check)
-fn ring_parameters_ethtool(interface: &str) -> Result<(u32, u32)> {
-    // create socket for ioctl
-    let fd = unsafe { socket(AF_INET, SOCK_DGRAM, 0) };
-    if fd < 0 {
-        return Err(anyhow::anyhow!("Failed to create socket"));
+/// RSS spread over the RX queues, and whether the queues AF_XDP sockets are
+/// bound to get any RSS traffic
+fn check_rss(interface: &str, rx_channels: Option<u32>) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let rss = match ethtool::rss(interface) {
+        Ok(rss) if !rss.indir.is_empty() => rss,
+        Ok(_) | Err(_) => {
+            results.push(CheckResult {
+                name: format!("{}: RSS", interface),
+                status: CheckStatus::Info,
+                message: "Driver does not report an RSS indirection table".to_string(),
+                details: None,
+            });
+            return results;
+        }
+    };
+
+    let mut weights: BTreeMap<u32, usize> = BTreeMap::new();
+    for queue in &rss.indir {
+        *weights.entry(*queue).or_default() += 1;
     }
-    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
 
-    let mut ring_param: EthtoolRingParam = unsafe { mem::zeroed() };
-    ring_param.cmd = ETHTOOL_GRINGPARAM;
+    let mut details = format!(
+        "Hash function: {}\nKey ({} bytes): {}",
+        RSS_HASH_FUNCS
+            .iter()
+            .filter(|(bit, _)| rss.hfunc & bit != 0)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join(", "),
+        rss.key.len(),
+        rss.key.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")
+    );
+    if let Ok(fields) = ethtool::rx_flow_hash(interface, ethtool::UDP_V4_FLOW) {
+        let names: Vec<&str> = RXH_FIELDS
+            .iter()
+            .filter(|(bit, _)| fields & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        details.push_str(&format!("\nUDP/IPv4 hash fields: {}", names.join(", ")));
+    }
+    details.push_str(&format!("\nIndirection table ({} entries):", rss.indir.len()));
+    for (queue, weight) in &weights {
+        details.push_str(&format!("\n  queue {}: {} entries", queue, weight));
+    }
 
-    let mut ifr: ifreq = unsafe { mem::zeroed() };
-    let if_name_bytes = interface.as_bytes();
-    let len = if_name_bytes.len().min(IF_NAMESIZE - 1);
-    unsafe {
-        ptr::copy_nonoverlapping(
-            if_name_bytes.as_ptr() as *const i8,
-            ifr.ifr_name.as_mut_ptr(),
-            len,
-        );
+    let unused: Vec<u32> = (0..rx_channels.unwrap_or(0)).filter(|q| !weights.contains_key(q)).collect();
+    if !unused.is_empty() {
+        details.push_str(&format!(
+            "\nQueues outside RSS: {}",
+            unused.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+        ));
     }
-    ifr.ifr_name[IF_NAMESIZE - 1] = 0;
-    ifr.ifr_ifru.ifru_data = &mut ring_param as *mut _ as *mut i8;
 
-    let res = unsafe { ioctl(fd.as_raw_fd(), SIOCETHTOOL, &ifr) };
-    if res < 0 {
-        return Err(anyhow::anyhow!("ethtool ioctl failed"));
+    results.push(CheckResult {
+        name: format!("{}: RSS", interface),
+        status: CheckStatus::Info,
+        message: format!(
+            "{} entries over {} queue(s){}",
+            rss.indir.len(),
+            weights.len(),
+            if unused.is_empty() {
+                String::new()
+            } else {
+                format!(", {} queue(s) excluded", unused.len())
+            }
+        ),
+        details: Some(details),
+    });
+
+    let ifindex = if_nametoindex(interface).unwrap_or(0);
+    let mut xsk_queues: Vec<u32> = xsk::xsk_sockets()
+        .unwrap_or_default()
+        .iter()
+        .filter(|s| s.ifindex == ifindex)
+        .map(|s| s.queue_id)
+        .collect();
+    xsk_queues.sort_unstable();
+    xsk_queues.dedup();
+
+    for queue in xsk_queues {
+        if weights.contains_key(&queue) {
+            results.push(CheckResult {
+                name: format!("{}: XSK Queue {}", interface, queue),
+                status: CheckStatus::Pass,
+                message: format!("Queue {} gets {} of {} RSS entries", queue, weights[&queue], rss.indir.len()),
+                details: None,
+            });
+        } else {
+            results.push(CheckResult {
+                name: format!("{}: XSK Queue {}", interface, queue),
+                status: CheckStatus::Warning,
+                message: format!("AF_XDP socket on queue {} gets no RSS traffic", queue),
+                details: Some(format!(
+                    "Only flow steering rules can deliver packets to it, e.g.\nethtool -N {} flow-type udp4 dst-port <port> action {}\nor spread RSS onto it with: ethtool -X {} equal <n>",
+                    interface, queue, interface
+                )),
+            });
+        }
     }
 
-    Ok((ring_param.rx_pending, ring_param.tx_pending))
-}
\ No newline at end of file
+    results
+}
+
+/// "512/4096", current over hardware maximum
+fn ring_size(current: Option<u32>, max: Option<u32>) -> String {
+    match (current, max) {
+        (Some(current), Some(max)) => format!("{}/{}", current, max),
+        (Some(current), None) => current.to_string(),
+        _ => "n/a".to_string(),
+    }
+}
+
+/// ring sizes against the hardware maximum and `min_rx_ring`, and against the
+/// rings of the AF_XDP sockets bound to the interface
+fn check_rings(interface: &str, min_rx_ring: u32) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let rings = match ethtool::rings(interface) {
+        Ok(rings) => rings,
+        Err(e) => {
+            results.push(CheckResult {
+                name: format!("{}: Ring Buffers", interface),
+                status: CheckStatus::Warning,
+                message: "Unable to determine ring buffer sizes".to_string(),
+                details: Some(format!("Could not query ring parameters via ethtool: {:#}", e)),
+            });
+            return results;
+        }
+    };
+
+    let Some(rx) = rings.rx else {
+        results.push(CheckResult {
+            name: format!("{}: Ring Buffers", interface),
+            status: CheckStatus::Info,
+            message: "Driver does not report an RX ring size".to_string(),
+            details: None,
+        });
+        return results;
+    };
+
+    let mut lines = Vec::new();
+    for (label, current, max) in [
+        ("RX mini", rings.rx_mini, rings.rx_mini_max),
+        ("RX jumbo", rings.rx_jumbo, rings.rx_jumbo_max),
+        ("TX push buffer", rings.tx_push_buf_len, rings.tx_push_buf_len_max),
+        ("Header split threshold", rings.hds_thresh, rings.hds_thresh_max),
+    ] {
+        if current.is_some() {
+            lines.push(format!("{}: {}", label, ring_size(current, max)));
+        }
+    }
+    if let Some(len) = rings.rx_buf_len {
+        lines.push(format!("RX buffer length: {}", len));
+    }
+    if let Some(split) = rings.tcp_data_split {
+        let name = TCP_DATA_SPLIT.iter().find(|(v, _)| *v == split).map_or("?", |(_, n)| *n);
+        lines.push(format!("TCP data split: {}", name));
+    }
+    if let Some(size) = rings.cqe_size {
+        lines.push(format!("CQE size: {}", size));
+    }
+    for (label, push) in [("TX push", rings.tx_push), ("RX push", rings.rx_push)] {
+        if let Some(push) = push {
+            lines.push(format!("{}: {}", label, if push { "on" } else { "off" }));
+        }
+    }
+
+    let rx_max = rings.rx_max.unwrap_or(rx);
+    let too_small = rx < min_rx_ring;
+    if too_small && rx < rx_max {
+        lines.push(format!(
+            "RX is below {} descriptors, bursts overflow it before NAPI drains it\nRaise it with: ethtool -G {} rx {}",
+            min_rx_ring,
+            interface,
+            min_rx_ring.min(rx_max)
+        ));
+    } else if too_small {
+        lines.push(format!(
+            "RX is below {} descriptors but already at the hardware maximum",
+            min_rx_ring
+        ));
+    } else if rx < rx_max {
+        lines.push(format!("RX can grow to {} if rx_missed or rx_dropped counters rise", rx_max));
+    }
+
+    results.push(CheckResult {
+        name: format!("{}: Ring Buffers", interface),
+        status: if too_small { CheckStatus::Warning } else { CheckStatus::Pass },
+        message: format!(
+            "RX: {}, TX: {} (current/max)",
+            ring_size(rings.rx, rings.rx_max),
+            ring_size(rings.tx, rings.tx_max)
+        ),
+        details: (!lines.is_empty()).then(|| lines.join("\n")),
+    });
+
+    let ifindex = if_nametoindex(interface).unwrap_or(0);
+    let mut sockets: Vec<xsk::XskInfo> = xsk::xsk_sockets()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| s.ifindex == ifindex)
+        .collect();
+    sockets.sort_by_key(|s| (s.queue_id, s.inode));
+
+    for socket in sockets {
+        let mut problems = Vec::new();
+        // in zero-copy mode the driver posts fill ring buffers to the NIC ring
+        if let Some(fill) = socket.fill_ring.filter(|fill| socket.zerocopy && *fill < rx) {
+            problems.push(format!(
+                "fill ring {} is smaller than the NIC RX ring {}, the driver can never populate the whole ring",
+                fill, rx
+            ));
+        }
+        if let Some(rx_ring) = socket.rx_ring.filter(|rx_ring| *rx_ring < rx) {
+            problems.push(format!(
+                "RX ring {} is smaller than the NIC RX ring {}, one NAPI poll can overflow it (rx_ring_full)",
+                rx_ring, rx
+            ));
+        }
+
+        results.push(CheckResult {
+            name: format!("{}: XSK Queue {} Rings", interface, socket.queue_id),
+            status: if problems.is_empty() { CheckStatus::Pass } else { CheckStatus::Warning },
+            message: format!(
+                "Socket {}: fill {}, RX {}, NIC RX {}",
+                socket.inode,
+                socket.fill_ring.map_or("n/a".to_string(), |n| n.to_string()),
+                socket.rx_ring.map_or("n/a".to_string(), |n| n.to_string()),
+                rx
+            ),
+            details: if problems.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "{}\nSize the socket rings to at least the NIC RX ring",
+                    problems.join("\n")
+                ))
+            },
+        });
+    }
+
+    results
+}
+
+/// queue and normalized name of a driver counter, None when it is not XDP or XSK related
+fn normalize_stat(driver: &str, name: &str) -> Option<(Option<u32>, String)> {
+    let prefixes = QUEUE_PREFIXES
+        .iter()
+        .find(|(d, _)| *d == driver)
+        .or_else(|| QUEUE_PREFIXES.iter().find(|(d, _)| d.is_empty()))
+        .map(|(_, prefixes)| *prefixes)
+        .unwrap_or_default();
+
+    let (queue, label, rest) = prefixes
+        .iter()
+        .find_map(|(pattern, label)| {
+            let (head, tail) = pattern.split_once("{}")?;
+            let after = name.strip_prefix(head)?;
+            let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+            let queue = after[..digits].parse::<u32>().ok()?;
+            let rest = after[digits..].strip_prefix(tail)?;
+            Some((Some(queue), *label, rest))
+        })
+        .unwrap_or((None, "", name));
+
+    let joined = if label.is_empty() || rest.starts_with(label) {
+        rest.to_string()
+    } else {
+        format!("{}_{}", label, rest)
+    };
+
+    let alias = |d: &str| {
+        STAT_ALIASES
+            .iter()
+            .find(|(alias_driver, from, _)| *alias_driver == d && *from == joined)
+            .map(|(_, _, to)| to.to_string())
+    };
+    let normalized = alias(driver).or_else(|| alias("")).unwrap_or(joined);
+
+    (normalized.contains("xdp") || normalized.contains("xsk")).then_some((queue, normalized))
+}
+
+/// XDP and XSK counters of an interface, names normalized for `driver`
+pub fn xdp_stats(interface: &str, driver: &str) -> Result<Vec<XdpStat>> {
+    Ok(ethtool::stats(interface)?
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let (queue, name) = normalize_stat(driver, &name)?;
+            Some(XdpStat { queue, name, value })
+        })
+        .collect())
+}
+
+/// counters that mean packets were lost, not dropped on purpose by the program
+pub fn is_error_stat(name: &str) -> bool {
+    ["err", "full", "invalid", "abort"].iter().any(|word| name.contains(word))
+}
+
+/// XDP and XSK counters from ethtool -S, device totals and per queue
+fn check_xdp_stats(interface: &str, driver: &str) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let stats = match xdp_stats(interface, driver) {
+        Ok(stats) => stats,
+        Err(e) => {
+            log::debug!("{}: ethtool stats failed: {:#}", interface, e);
+            return results;
+        }
+    };
+
+    if stats.is_empty() {
+        results.push(CheckResult {
+            name: format!("{}: XDP Stats", interface),
+            status: CheckStatus::Info,
+            message: "Driver exposes no XDP counters".to_string(),
+            details: None,
+        });
+        return results;
+    }
+
+    let describe = |stats: &[&XdpStat]| -> String {
+        stats
+            .iter()
+            .map(|s| format!("{}: {}", s.name, s.value))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // device totals: the driver-wide counter when there is one, it already
+    // covers every queue (mlx5 reports both), otherwise the sum over queues
+    let mut totals: Vec<(String, u64)> = Vec::new();
+    for stat in &stats {
+        if totals.iter().any(|(name, _)| *name == stat.name) {
+            continue;
+        }
+        let total = match stats.iter().find(|s| s.queue.is_none() && s.name == stat.name) {
+            Some(device) => device.value,
+            None => stats.iter().filter(|s| s.name == stat.name).map(|s| s.value).sum(),
+        };
+        totals.push((stat.name.clone(), total));
+    }
+    let errors: Vec<String> = totals
+        .iter()
+        .filter(|(name, value)| *value > 0 && is_error_stat(name))
+        .map(|(name, value)| format!("{} {}", name, value))
+        .collect();
+    let active: Vec<String> = totals
+        .iter()
+        .filter(|(_, value)| *value > 0)
+        .map(|(name, value)| format!("{} {}", name, value))
+        .collect();
+
+    results.push(CheckResult {
+        name: format!("{}: XDP Stats", interface),
+        status: if errors.is_empty() { CheckStatus::Info } else { CheckStatus::Warning },
+        message: if !errors.is_empty() {
+            format!("Errors: {}", errors.join(", "))
+        } else if active.is_empty() {
+            "All XDP counters are zero".to_string()
+        } else {
+            active.join(", ")
+        },
+        details: Some(
+            totals
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    });
+
+    let mut queues: Vec<u32> = stats.iter().filter_map(|s| s.queue).collect();
+    queues.sort_unstable();
+    queues.dedup();
+
+    for queue in queues {
+        let queue_stats: Vec<&XdpStat> = stats.iter().filter(|s| s.queue == Some(queue)).collect();
+        let nonzero: Vec<&XdpStat> = queue_stats.iter().copied().filter(|s| s.value > 0).collect();
+        let has_errors = nonzero.iter().any(|s| is_error_stat(&s.name));
+
+        results.push(CheckResult {
+            name: format!("{}: Queue {} XDP", interface, queue),
+            status: if has_errors { CheckStatus::Warning } else { CheckStatus::Info },
+            message: if nonzero.is_empty() {
+                "No XDP activity".to_string()
+            } else {
+                nonzero
+                    .iter()
+                    .map(|s| format!("{} {}", s.name, s.value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            },
+            details: Some(describe(&queue_stats)),
+        });
+    }
+
+    results
+}
@@ -12,7 +12,7 @@ pub enum CheckStatus {
 }
 
 impl CheckStatus {
-    pub fn to_icon(&self) -> String {
+    pub fn to_icon(self) -> String {
         match self {
             CheckStatus::Pass => "✓".green().to_string(),
             CheckStatus::Fail => "✗".red().to_string(),
@@ -23,7 +23,7 @@ impl CheckStatus {
     }
 
     #[allow(dead_code)]
-    pub fn to_text(&self) -> String {
+    pub fn to_text(self) -> String {
         match self {
             CheckStatus::Pass => "PASS".green().to_string(),
             CheckStatus::Fail => "FAIL".red().to_string(),
@@ -33,7 +33,7 @@ impl CheckStatus {
         }
     }
 
-    pub fn is_failure(&self) -> bool {
+    pub fn is_failure(self) -> bool {
         matches!(self, CheckStatus::Fail | CheckStatus::Error)
     }
 }
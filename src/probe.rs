@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Context, Result};
+use aya::programs::{Xdp, XdpFlags};
+use aya::Ebpf;
+use nix::sched::{unshare, CloneFlags};
+use std::thread;
+
+use crate::netlink::{self, XdpMode};
+use crate::output::{CheckResult, CheckStatus};
+use crate::xsk;
+
+/// veth pair created inside the scratch namespace
+const PROBE_IFACE: &str = "xdpchk0";
+const PROBE_PEER: &str = "xdpchk1";
+
+/// `return XDP_PASS;`, see bpf/xdp_pass.c
+static XDP_PASS_OBJ: &[u8] = aya::include_bytes_aligned!("../bpf/xdp_pass.o");
+
+/// load and attach a real XDP program on a throwaway veth pair
+pub fn probe_xdp() -> Result<Vec<CheckResult>> {
+    with_scratch_veth(probe_attach)
+}
+
+/// bind an AF_XDP socket to `interface`, or to a scratch veth when none is given
+pub fn probe_xsk(interface: Option<&str>, queue: u32) -> Result<Vec<CheckResult>> {
+    match interface {
+        Some(interface) => Ok(xsk::probe_bind(interface, queue, true)),
+        // veth has no zero-copy support, the copy fallback is what we test
+        None => with_scratch_veth(|iface| xsk::probe_bind(iface, 0, false)),
+    }
+}
+
+/// run `probe` against a veth pair in a temporary network namespace
+fn with_scratch_veth<F>(probe: F) -> Result<Vec<CheckResult>>
+where
+    F: FnOnce(&str) -> Vec<CheckResult> + Send + 'static,
+{
+    // unshare only moves the calling thread, so the host namespace of the
+    // main thread is never touched and the scratch one dies with the thread
+    thread::spawn(move || {
+        let mut results = Vec::new();
+
+        if let Err(e) = unshare(CloneFlags::CLONE_NEWNET) {
+            results.push(step_result(
+                "Scratch Namespace",
+                Err(anyhow!("unshare(CLONE_NEWNET) failed: {} (needs CAP_SYS_ADMIN)", e)),
+            ));
+            return results;
+        }
+        results.push(step_result(
+            "Scratch Namespace",
+            Ok("Created temporary network namespace".to_string()),
+        ));
+
+        if let Err(e) = netlink::create_veth(PROBE_IFACE, PROBE_PEER) {
+            results.push(step_result("Veth Pair", Err(e)));
+            return results;
+        }
+        results.push(step_result(
+            "Veth Pair",
+            Ok(format!("Created {} <-> {}", PROBE_IFACE, PROBE_PEER)),
+        ));
+
+        results.extend(probe(PROBE_IFACE));
+
+        results.push(step_result(
+            "Cleanup",
+            netlink::delete_link(PROBE_IFACE)
+                .map(|_| format!("Removed {} and {}", PROBE_IFACE, PROBE_PEER)),
+        ));
+
+        results
+    })
+    .join()
+    .map_err(|_| anyhow!("Probe thread panicked"))
+}
+
+fn probe_attach(interface: &str) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let mut bpf = match Ebpf::load(XDP_PASS_OBJ) {
+        Ok(bpf) => bpf,
+        Err(e) => {
+            results.push(step_result("Program Load", Err(anyhow!(e).context("Failed to parse object"))));
+            return results;
+        }
+    };
+
+    let program: &mut Xdp = match bpf
+        .program_mut("xdp_pass")
+        .ok_or_else(|| anyhow!("xdp_pass not found in object"))
+        .and_then(|p| p.try_into().context("xdp_pass is not an XDP program"))
+    {
+        Ok(program) => program,
+        Err(e) => {
+            results.push(step_result("Program Load", Err(e)));
+            return results;
+        }
+    };
+
+    if let Err(e) = program.load() {
+        results.push(step_result(
+            "Program Load",
+            Err(anyhow!(e).context("Verifier rejected XDP_PASS program")),
+        ));
+        return results;
+    }
+
+    let prog_id = program.info().map(|info| info.id()).unwrap_or(0);
+    results.push(step_result(
+        "Program Load",
+        Ok(format!("XDP_PASS program accepted by the verifier (ID: {})", prog_id)),
+    ));
+
+    for (name, detach_name, flags, mode) in [
+        ("Generic Attach", "Generic Detach", XdpFlags::SKB_MODE, XdpMode::Generic),
+        ("Native Attach", "Native Detach", XdpFlags::DRV_MODE, XdpMode::Native),
+    ] {
+        let link_id = match program.attach(interface, flags) {
+            Ok(link_id) => link_id,
+            Err(e) => {
+                results.push(step_result(name, Err(anyhow!(e).context(format!("{} attach failed", mode.as_str())))));
+                continue;
+            }
+        };
+
+        // confirm the kernel reports the mode we asked for
+        let attached = netlink::xdp_attachment(interface)
+            .map(|a| a.map(|a| a.programs()).unwrap_or_default())
+            .map(|programs| programs.contains(&(mode, prog_id)))
+            .unwrap_or(false);
+
+        results.push(step_result(
+            name,
+            if attached {
+                Ok(format!("Attached in {} mode on {}", mode.as_str(), interface))
+            } else {
+                Err(anyhow!("Attach returned success but IFLA_XDP does not report {} mode", mode.as_str()))
+            },
+        ));
+
+        results.push(step_result(
+            detach_name,
+            program
+                .detach(link_id)
+                .map(|_| format!("Detached {} program from {}", mode.as_str(), interface))
+                .map_err(|e| anyhow!(e).context("Detach failed")),
+        ));
+    }
+
+    results
+}
+
+fn step_result(name: &str, outcome: Result<String>) -> CheckResult {
+    match outcome {
+        Ok(message) => CheckResult {
+            name: format!("Probe: {}", name),
+            status: CheckStatus::Pass,
+            message,
+            details: None,
+        },
+        Err(e) => CheckResult {
+            name: format!("Probe: {}", name),
+            status: CheckStatus::Fail,
+            message: e.to_string(),
+            details: Some(format!("{:#}", e)),
+        },
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::{bail, Result};
+use aya::maps::MapInfo;
+use aya::programs::{loaded_programs, ProgramInfo};
+use std::fs;
+use std::os::unix::io::AsFd;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bpf;
+use crate::output::{CheckResult, CheckStatus};
+
+/// map_flags bits worth naming, from include/uapi/linux/bpf.h
+const MAP_FLAGS: &[(u32, &str)] = &[
+    (1 << 0, "NO_PREALLOC"),
+    (1 << 1, "NO_COMMON_LRU"),
+    (1 << 2, "NUMA_NODE"),
+    (1 << 3, "RDONLY"),
+    (1 << 4, "WRONLY"),
+    (1 << 5, "STACK_BUILD_ID"),
+    (1 << 6, "ZERO_SEED"),
+    (1 << 7, "RDONLY_PROG"),
+    (1 << 8, "WRONLY_PROG"),
+    (1 << 9, "CLONE"),
+    (1 << 10, "MMAPABLE"),
+    (1 << 11, "PRESERVE_ELEMS"),
+    (1 << 12, "INNER_MAP"),
+];
+
+/// details of every loaded program whose ID or name matches `selector`
+pub fn inspect_program(selector: &str) -> Result<Vec<CheckResult>> {
+    let id = selector.parse::<u32>().ok();
+
+    let programs: Vec<ProgramInfo> = loaded_programs()
+        .filter_map(|r| r.ok())
+        .filter(|p| match id {
+            Some(id) => p.id() == id,
+            None => p.name_as_str() == Some(selector),
+        })
+        .collect();
+
+    if programs.is_empty() {
+        bail!("No loaded BPF program with ID or name '{}'", selector);
+    }
+
+    Ok(programs.iter().flat_map(program_results).collect())
+}
+
+fn program_results(prog: &ProgramInfo) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    let label = format!("prog {}", prog.id());
+    let name = prog.name_as_str().filter(|n| !n.is_empty()).unwrap_or("(unnamed)");
+
+    let prog_type = prog
+        .program_type()
+        .map(|t| format!("{:?}", t))
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    results.push(CheckResult {
+        name: format!("{}: Program", label),
+        status: CheckStatus::Info,
+        message: format!("{} ({}, ID: {})", name, prog_type, prog.id()),
+        details: Some(format!(
+            "Tag: {:016x}\nGPL compatible: {}\nBTF ID: {}",
+            prog.tag(),
+            prog.gpl_compatible().map_or("unknown", |gpl| if gpl { "yes" } else { "no" }),
+            prog.btf_id().map_or("none".to_string(), |id| id.to_string())
+        )),
+    });
+
+    let translated = prog.size_translated().unwrap_or(0);
+    results.push(CheckResult {
+        name: format!("{}: Instructions", label),
+        status: CheckStatus::Info,
+        message: format!(
+            "Translated: {} insns, JITed: {} bytes",
+            translated / 8,
+            prog.size_jitted()
+        ),
+        details: Some(format!(
+            "Translated size: {} bytes\nVerified insns: {}\nMemlock: {}",
+            translated,
+            prog.verified_instruction_count()
+                .map_or("unknown".to_string(), |n| n.to_string()),
+            prog.memory_locked()
+                .map_or("unknown".to_string(), |b| format!("{} bytes", b))
+        )),
+    });
+
+    results.push(CheckResult {
+        name: format!("{}: Loaded", label),
+        status: CheckStatus::Info,
+        message: match prog.loaded_at() {
+            Some(at) => format!("Loaded {}", describe_time(at)),
+            None => "Load time unknown".to_string(),
+        },
+        details: Some(format!(
+            "UID: {}",
+            prog.created_by_uid().map_or("unknown".to_string(), |uid| uid.to_string())
+        )),
+    });
+
+    results.push(run_stats(&label, prog));
+
+    match prog.fd().map_err(anyhow::Error::from).and_then(|fd| bpf::prog_func_info(fd.as_fd())) {
+        Ok(funcs) if funcs.is_empty() => results.push(CheckResult {
+            name: format!("{}: BTF Functions", label),
+            status: CheckStatus::Info,
+            message: "No BTF func info (loaded without BTF)".to_string(),
+            details: None,
+        }),
+        Ok(funcs) => results.push(CheckResult {
+            name: format!("{}: BTF Functions", label),
+            status: CheckStatus::Info,
+            message: funcs.iter().map(|f| f.name.as_str()).collect::<Vec<_>>().join(", "),
+            details: Some(
+                funcs
+                    .iter()
+                    .map(|f| format!("insn {:>5}: {}", f.insn_off, f.name))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }),
+        Err(e) => results.push(CheckResult {
+            name: format!("{}: BTF Functions", label),
+            status: CheckStatus::Warning,
+            message: "Unable to read BTF func info".to_string(),
+            details: Some(format!("{:#}", e)),
+        }),
+    }
+
+    match prog.map_ids() {
+        Ok(Some(map_ids)) if !map_ids.is_empty() => {
+            for map_id in map_ids {
+                results.push(map_result(&label, map_id));
+            }
+        }
+        Ok(_) => results.push(CheckResult {
+            name: format!("{}: Maps", label),
+            status: CheckStatus::Info,
+            message: "Program uses no maps".to_string(),
+            details: None,
+        }),
+        Err(e) => results.push(CheckResult {
+            name: format!("{}: Maps", label),
+            status: CheckStatus::Warning,
+            message: "Unable to list maps".to_string(),
+            details: Some(e.to_string()),
+        }),
+    }
+
+    results
+}
+
+fn run_stats(label: &str, prog: &ProgramInfo) -> CheckResult {
+    let enabled = fs::read_to_string("/proc/sys/kernel/bpf_stats_enabled")
+        .map(|v| v.trim() != "0")
+        .unwrap_or(false);
+
+    let run_count = prog.run_count();
+    let run_time = prog.run_time().as_nanos();
+
+    let message = if run_count > 0 {
+        format!(
+            "{} runs, {} ns total, {} ns/run",
+            run_count,
+            run_time,
+            run_time / run_count as u128
+        )
+    } else if enabled {
+        "No runs recorded".to_string()
+    } else {
+        "Run stats disabled".to_string()
+    };
+
+    CheckResult {
+        name: format!("{}: Run Stats", label),
+        status: CheckStatus::Info,
+        message,
+        details: Some(if enabled {
+            "Collected while kernel.bpf_stats_enabled=1".to_string()
+        } else {
+            "Enable with: sysctl -w kernel.bpf_stats_enabled=1 (adds overhead per run)".to_string()
+        }),
+    }
+}
+
+fn map_result(label: &str, map_id: u32) -> CheckResult {
+    let map = match MapInfo::from_id(map_id) {
+        Ok(map) => map,
+        Err(e) => {
+            return CheckResult {
+                name: format!("{}: Map {}", label, map_id),
+                status: CheckStatus::Warning,
+                message: format!("Unable to read map {}", map_id),
+                details: Some(e.to_string()),
+            };
+        }
+    };
+
+    let map_type = map
+        .map_type()
+        .map(|t| format!("{:?}", t))
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let flags = map.map_flags();
+    let mut flag_names: Vec<&str> = MAP_FLAGS
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if flag_names.is_empty() {
+        flag_names.push("none");
+    }
+
+    CheckResult {
+        name: format!("{}: Map {}", label, map_id),
+        status: CheckStatus::Info,
+        message: format!(
+            "{} {} max_entries {}",
+            map.name_as_str().filter(|n| !n.is_empty()).unwrap_or("(unnamed)"),
+            map_type,
+            map.max_entries()
+        ),
+        details: Some(format!(
+            "Key size: {} bytes\nValue size: {} bytes\nFlags: {:#x} ({})",
+            map.key_size(),
+            map.value_size(),
+            flags,
+            flag_names.join(", ")
+        )),
+    }
+}
+
+/// "2h 3m 10s ago (unix 1712345678)"
+fn describe_time(at: SystemTime) -> String {
+    let unix = at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let Ok(elapsed) = SystemTime::now().duration_since(at) else {
+        return format!("at unix {}", unix);
+    };
+
+    let secs = elapsed.as_secs();
+    let ago = match (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60) {
+        (0, 0, 0, s) => format!("{}s", s),
+        (0, 0, m, s) => format!("{}m {}s", m, s),
+        (0, h, m, s) => format!("{}h {}m {}s", h, m, s),
+        (d, h, m, _) => format!("{}d {}h {}m", d, h, m),
+    };
+
+    format!("{} ago (unix {})", ago, unix)
+}
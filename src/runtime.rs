@@ -1,11 +1,17 @@
 use anyhow::Result;
-use std::fs;
+use std::collections::HashMap;
 use std::path::Path;
-use aya::programs::loaded_programs;
+use aya::programs::{loaded_programs, ProgramInfo};
 
+use crate::bpffs;
+use crate::expect::{self, ExpectedProgram};
+use crate::links;
+use crate::netlink;
 use crate::output::{CheckResult, CheckStatus};
+use crate::xsk;
 
-pub fn check_xdp_runtime(interface: Option<&str>) -> Result<Vec<CheckResult>> {
+/// with `fix`, a missing /sys/fs/bpf is mounted
+pub fn check_xdp_runtime(interface: Option<&str>, expected: &[ExpectedProgram], fix: bool) -> Result<Vec<CheckResult>> {
     let mut results = Vec::new();
 
     if let Some(iface) = interface {
@@ -14,15 +20,119 @@ pub fn check_xdp_runtime(interface: Option<&str>) -> Result<Vec<CheckResult>> {
         results.extend(check_all_xdp_runtime()?);
     }
 
-    results.push(check_xsk_sockets());
+    results.extend(links::check_links(interface));
 
-    results.extend(check_bpf_programs()?);
+    results.extend(expect::check_expected_programs(expected, interface));
+
+    results.extend(check_xsk_sockets());
+
+    results.extend(check_bpf_programs(fix)?);
 
     Ok(results)
 }
 
 fn check_all_xdp_runtime() -> Result<Vec<CheckResult>> {
-    Ok(Vec::new())
+    let mut results = Vec::new();
+
+    log::debug!("Dumping XDP attachments for all interfaces");
+
+    let attachments = match netlink::xdp_attachments() {
+        Ok(attachments) => attachments,
+        Err(e) => {
+            results.push(CheckResult {
+                name: "XDP Attachments".to_string(),
+                status: CheckStatus::Error,
+                message: "Unable to query XDP attachments".to_string(),
+                details: Some(format!("RTM_GETLINK dump failed: {:#}", e)),
+            });
+            return Ok(results);
+        }
+    };
+
+    let xdp_programs: HashMap<u32, ProgramInfo> = loaded_programs()
+        .filter_map(|r| r.ok())
+        .filter(|p| {
+            matches!(p.program_type(), Ok(aya::programs::ProgramType::Xdp))
+        })
+        .map(|p| (p.id(), p))
+        .collect();
+
+    // prog ID -> interfaces it is attached to
+    let mut attached_to: HashMap<u32, Vec<String>> = HashMap::new();
+
+    for attachment in &attachments {
+        if attachment.name == "lo" && !attachment.is_attached() {
+            continue;
+        }
+
+        let programs = attachment.programs();
+        if programs.is_empty() {
+            results.push(CheckResult {
+                name: format!("{}: XDP Program", attachment.name),
+                status: CheckStatus::Info,
+                message: "No XDP program attached".to_string(),
+                details: None,
+            });
+            continue;
+        }
+
+        for (mode, id) in programs {
+            attached_to
+                .entry(id)
+                .or_default()
+                .push(format!("{} ({})", attachment.name, mode.as_str()));
+
+            let (name, tag) = match xdp_programs.get(&id) {
+                Some(prog) => (
+                    prog.name_as_str().unwrap_or("?").to_string(),
+                    format!("{:016x}", prog.tag()),
+                ),
+                None => ("?".to_string(), "?".to_string()),
+            };
+
+            results.push(CheckResult {
+                name: format!("{}: XDP Program", attachment.name),
+                status: CheckStatus::Pass,
+                message: format!("{} attached in {} mode (ID: {})", name, mode.as_str(), id),
+                details: Some(format!(
+                    "Program ID: {}\nProgram Name: {}\nProgram Tag: {}\nMode: {}",
+                    id,
+                    name,
+                    tag,
+                    mode.as_str()
+                )),
+            });
+        }
+    }
+
+    let mut ids: Vec<_> = xdp_programs.keys().copied().collect();
+    ids.sort_unstable();
+
+    for id in ids {
+        let prog = &xdp_programs[&id];
+        let name = prog.name_as_str().unwrap_or("?");
+        let tag = format!("{:016x}", prog.tag());
+
+        match attached_to.get(&id) {
+            Some(interfaces) => results.push(CheckResult {
+                name: format!("XDP Program {}", id),
+                status: CheckStatus::Info,
+                message: format!("{} attached to {}", name, interfaces.join(", ")),
+                details: Some(format!("Program Tag: {}", tag)),
+            }),
+            None => results.push(CheckResult {
+                name: format!("XDP Program {}", id),
+                status: CheckStatus::Warning,
+                message: format!("{} is loaded but not attached to any interface", name),
+                details: Some(format!(
+                    "Program Tag: {}\nThe program may be held by a pin, a map or a stale loader process",
+                    tag
+                )),
+            }),
+        }
+    }
+
+    Ok(results)
 }
 
 fn check_interface_xdp_runtime(interface: &str) -> Result<Vec<CheckResult>> {
@@ -42,191 +152,149 @@ fn check_interface_xdp_runtime(interface: &str) -> Result<Vec<CheckResult>> {
         return Ok(results);
     }
 
-    // Query all XDP programs to see if any are loaded
-    let xdp_programs: Vec<_> = loaded_programs()
-        .filter_map(|r| r.ok())
-        .filter(|p| {
-            matches!(p.program_type(), Ok(aya::programs::ProgramType::Xdp))
-        })
-        .collect();
+    let attachment = match netlink::xdp_attachment(interface) {
+        Ok(attachment) => attachment,
+        Err(e) => {
+            results.push(CheckResult {
+                name: format!("{}: XDP Program", interface),
+                status: CheckStatus::Error,
+                message: "Unable to query XDP attachment".to_string(),
+                details: Some(format!("RTM_GETLINK failed: {:#}", e)),
+            });
+            return Ok(results);
+        }
+    };
 
-    if xdp_programs.is_empty() {
+    let programs = attachment.map(|a| a.programs()).unwrap_or_default();
+    if programs.is_empty() {
         results.push(CheckResult {
             name: format!("{}: XDP Program", interface),
             status: CheckStatus::Info,
-            message: "No XDP programs loaded in the system".to_string(),
+            message: "No XDP program attached".to_string(),
             details: None,
         });
         return Ok(results);
     }
 
-    // Find agave_xdp programs
-    let agave_programs: Vec<_> = xdp_programs
-        .iter()
-        .filter(|p| p.name_as_str().unwrap_or("") == "agave_xdp")
+    let xdp_programs: HashMap<u32, ProgramInfo> = loaded_programs()
+        .filter_map(|r| r.ok())
+        .map(|p| (p.id(), p))
         .collect();
 
-    if !agave_programs.is_empty() {
-        for prog in agave_programs {
-            let tag = format!("{:016x}", prog.tag());
-            results.push(CheckResult {
-                name: format!("{}: XDP Program", interface),
-                status: CheckStatus::Pass,
-                message: format!("agave_xdp program loaded (ID: {})", prog.id()),
-                details: Some(format!("Program Tag: {}\nType: XDP", tag)),
-            });
-        }
-    } else {
+    for (mode, id) in programs {
+        let (name, tag) = match xdp_programs.get(&id) {
+            Some(prog) => (
+                prog.name_as_str().unwrap_or("?").to_string(),
+                format!("{:016x}", prog.tag()),
+            ),
+            None => ("?".to_string(), "?".to_string()),
+        };
+
         results.push(CheckResult {
             name: format!("{}: XDP Program", interface),
-            status: CheckStatus::Warning,
-            message: format!("{} XDP program(s) loaded, but none named 'agave_xdp'", xdp_programs.len()),
-            details: Some("Other XDP programs found but 'agave_xdp' is not loaded".to_string()),
+            status: CheckStatus::Pass,
+            message: format!("{} attached in {} mode (ID: {})", name, mode.as_str(), id),
+            details: Some(format!("Program Tag: {}\nType: XDP", tag)),
         });
     }
 
     Ok(results)
 }
 
-fn check_xsk_sockets() -> CheckResult {
-    // /proc/net/xsk for AF_XDP socket information
-    let xsk_path = "/proc/net/xsk";
-
-    if Path::new(xsk_path).exists() {
-        if let Ok(content) = fs::read_to_string(xsk_path) {
-            let lines: Vec<&str> = content.lines().collect();
-            if lines.len() > 1 {  // header
-                return CheckResult {
-                    name: "AF_XDP Sockets".to_string(),
-                    status: CheckStatus::Pass,
-                    message: format!("{} AF_XDP socket(s) active", lines.len() - 1),
-                    details: Some("Active AF_XDP sockets detected".to_string()),
-                };
-            }
-        }
-    }
-
-    // alternative check: look for xsk_diag module
-    if let Ok(modules) = fs::read_to_string("/proc/modules") {
-        if modules.contains("xsk_diag") {
-            return CheckResult {
-                name: "AF_XDP Support".to_string(),
-                status: CheckStatus::Info,
-                message: "XSK diagnostic module loaded".to_string(),
-                details: Some("AF_XDP support available but no active sockets".to_string()),
-            };
-        }
-    }
-
-    CheckResult {
-        name: "AF_XDP Sockets".to_string(),
-        status: CheckStatus::Info,
-        message: "No AF_XDP sockets detected".to_string(),
-        details: Some("AF_XDP socket monitoring may not be available".to_string()),
-    }
-}
-
-/// Check for BPF programs
-fn check_bpf_programs() -> Result<Vec<CheckResult>> {
+fn check_xsk_sockets() -> Vec<CheckResult> {
     let mut results = Vec::new();
 
-    log::debug!("Querying loaded XDP programs via aya");
-
-    // Use aya to list XDP programs
-    let xdp_programs: Vec<_> = loaded_programs()
-        .filter_map(|r| r.ok())
-        .filter(|p| {
-            matches!(p.program_type(), Ok(aya::programs::ProgramType::Xdp))
-        })
-        .collect();
-
-    log::debug!("Found {} XDP program(s)", xdp_programs.len());
-
-    // Find agave_xdp programs
-    let agave_programs: Vec<_> = xdp_programs
-        .iter()
-        .filter(|p| p.name_as_str().unwrap_or("") == "agave_xdp")
-        .collect();
-
-    // Report on agave_xdp programs
-    if !agave_programs.is_empty() {
-        for prog in &agave_programs {
-            let tag = format!("{:016x}", prog.tag());
-            let details = format!(
-                "Program ID: {}\nProgram Tag: {}\nType: XDP",
-                prog.id(),
-                tag
-            );
-
+    let sockets = match xsk::xsk_sockets() {
+        Ok(sockets) => sockets,
+        Err(e) => {
+            log::debug!("xdp_diag dump failed: {:#}", e);
             results.push(CheckResult {
-                name: "Agave XDP Program".to_string(),
-                status: CheckStatus::Pass,
-                message: format!("agave_xdp is loaded and active (ID: {})", prog.id()),
-                details: Some(details),
+                name: "AF_XDP Sockets".to_string(),
+                status: CheckStatus::Info,
+                message: "Unable to query AF_XDP sockets".to_string(),
+                details: Some(format!(
+                    "NETLINK_SOCK_DIAG xdp_diag request failed: {:#}\nLoad the xsk_diag module (CONFIG_XDP_SOCKETS_DIAG) to enable socket monitoring",
+                    e
+                )),
             });
-
-            log::debug!("  ✓ agave_xdp: ID {} tag {}", prog.id(), tag);
+            return results;
         }
-    } else if !xdp_programs.is_empty() {
-        // Other XDP programs exist but no agave_xdp
-        results.push(CheckResult {
-            name: "Agave XDP Program".to_string(),
-            status: CheckStatus::Warning,
-            message: format!("{} XDP program(s) loaded, but none named 'agave_xdp'", xdp_programs.len()),
-            details: Some("Other XDP programs detected but 'agave_xdp' not found".to_string()),
-        });
+    };
 
-        for prog in &xdp_programs {
-            log::debug!("  - Other XDP Program: ID {} name '{}'",
-                prog.id(),
-                prog.name_as_str().unwrap_or("?")
-            );
-        }
-    } else {
-        // No XDP programs at all
+    if sockets.is_empty() {
         results.push(CheckResult {
-            name: "Agave XDP Program".to_string(),
+            name: "AF_XDP Sockets".to_string(),
             status: CheckStatus::Info,
-            message: "No XDP programs currently loaded".to_string(),
+            message: "No AF_XDP sockets detected".to_string(),
             details: None,
         });
+        return results;
     }
 
-    // check /sys/fs/bpf for pinned programs
-    let bpf_fs = "/sys/fs/bpf";
-    if Path::new(bpf_fs).exists() {
-        let mut pinned_count = 0;
-        if let Ok(entries) = fs::read_dir(bpf_fs) {
-            for entry in entries.flatten() {
-                if let Some(name) = entry.file_name().to_str() {
-                    if name.contains("xdp") || name.contains("xsk") {
-                        pinned_count += 1;
-                    }
-                }
-            }
-        }
+    let owners = xsk::socket_owners();
 
-        if pinned_count > 0 {
-            results.push(CheckResult {
-                name: "Pinned BPF Programs".to_string(),
-                status: CheckStatus::Info,
-                message: format!("{} XDP-related pinned program(s)", pinned_count),
-                details: Some(format!("Found in {}", bpf_fs)),
-            });
-        }
-    }
+    results.push(CheckResult {
+        name: "AF_XDP Sockets".to_string(),
+        status: CheckStatus::Pass,
+        message: format!("{} AF_XDP socket(s) active", sockets.len()),
+        details: None,
+    });
+
+    for socket in &sockets {
+        let owner = match owners.get(&(socket.inode as u64)) {
+            Some(procs) => procs
+                .iter()
+                .map(|(pid, comm)| format!("{} (PID {})", comm, pid))
+                .collect::<Vec<_>>()
+                .join(", "),
+            None => "unknown".to_string(),
+        };
+
+        let ring = |entries: Option<u32>| entries.map(|e| e.to_string()).unwrap_or_else(|| "-".to_string());
+        let errors = socket.rx_dropped + socket.rx_invalid + socket.tx_invalid;
 
-    // check if BPF filesystem is mounted
-    if let Ok(mounts) = fs::read_to_string("/proc/mounts") {
-        if mounts.contains("bpf") {
-            results.push(CheckResult {
-                name: "BPF Filesystem".to_string(),
-                status: CheckStatus::Pass,
-                message: "BPF filesystem mounted".to_string(),
-                details: Some("BPF filesystem is available for pinning programs".to_string()),
-            });
-        }
+        results.push(CheckResult {
+            name: format!("XSK {}", socket.inode),
+            status: if errors > 0 { CheckStatus::Warning } else { CheckStatus::Info },
+            message: format!(
+                "{} queue {} ({}) owned by {}",
+                socket.interface(),
+                socket.queue_id,
+                if socket.zerocopy { "zero-copy" } else { "copy" },
+                owner
+            ),
+            details: Some(format!(
+                "Inode: {}\nUID: {}\nUMEM: id {}, {} bytes, chunk {}, headroom {}\nRings: rx {}, tx {}, fill {}, completion {}\nrx_dropped: {}, rx_invalid: {}, tx_invalid: {}\nrx_ring_full: {}, fill_ring_empty: {}, tx_ring_empty: {}",
+                socket.inode,
+                socket.uid.map(|u| u.to_string()).unwrap_or_else(|| "-".to_string()),
+                socket.umem_id,
+                socket.umem_size,
+                socket.chunk_size,
+                socket.headroom,
+                ring(socket.rx_ring),
+                ring(socket.tx_ring),
+                ring(socket.fill_ring),
+                ring(socket.completion_ring),
+                socket.rx_dropped,
+                socket.rx_invalid,
+                socket.tx_invalid,
+                socket.rx_full,
+                socket.fill_ring_empty,
+                socket.tx_ring_empty,
+            )),
+        });
     }
 
+    results
+}
+
+/// Check for BPF programs
+fn check_bpf_programs(fix: bool) -> Result<Vec<CheckResult>> {
+    let mut results = Vec::new();
+
+    results.extend(bpffs::check_mounts(fix));
+
+    results.extend(bpffs::check_pins());
+
     Ok(results)
-}
\ No newline at end of file
+}
@@ -3,9 +3,40 @@ use std::fs;
 use std::path::Path;
 use nix::sys::resource;
 
+use crate::ethtool::{self, Coalesce};
 use crate::output::{CheckResult, CheckStatus};
 
-pub fn check_system_resources() -> Result<Vec<CheckResult>> {
+/// rx-usecs above this adds noticeable delay to every packet
+const LATENCY_MAX_RX_USECS: u32 = 8;
+/// rx-usecs below this interrupts for too few packets to batch
+const THROUGHPUT_MIN_RX_USECS: u32 = 32;
+/// busy_poll and busy_read suggested for the latency profile, in µs
+const BUSY_POLL_USECS: u32 = 50;
+/// napi_defer_hard_irqs and gro_flush_timeout (ns) from the kernel's napi busy polling docs
+const NAPI_DEFER_HARD_IRQS: u32 = 2;
+const GRO_FLUSH_TIMEOUT_NS: u32 = 200_000;
+/// details of the tuning checks when no --profile is given
+const NO_PROFILE: &str = "Not checked, pass --profile latency or --profile throughput to compare against a profile";
+
+/// what interrupt coalescing and busy polling should be tuned for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum TuningProfile {
+    /// busy polling, deferred IRQs, short fixed coalescing
+    Latency,
+    /// interrupt driven, adaptive or long coalescing
+    Throughput,
+}
+
+impl TuningProfile {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TuningProfile::Latency => "latency",
+            TuningProfile::Throughput => "throughput",
+        }
+    }
+}
+
+pub fn check_system_resources(profile: Option<TuningProfile>) -> Result<Vec<CheckResult>> {
     let mut results = Vec::new();
 
     results.push(check_huge_pages()?);
@@ -14,6 +45,8 @@ pub fn check_system_resources() -> Result<Vec<CheckResult>> {
 
     results.extend(check_cpu_info()?);
 
+    results.push(check_busy_poll(profile));
+
     results.push(check_irq_affinity());
 
     results.push(check_system_load()?);
@@ -159,6 +192,256 @@ fn check_cpu_info() -> Result<Vec<CheckResult>> {
     Ok(results)
 }
 
+/// coalescing and NAPI deferral of one interface, against `profile` when one
+/// is given, otherwise only reported
+pub fn check_interface_tuning(interface: &str, profile: Option<TuningProfile>) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    // veth, bridges and other virtual devices have no interrupts to tune
+    if !Path::new(&format!("/sys/class/net/{}/device", interface)).exists() {
+        return results;
+    }
+
+    match ethtool::coalesce(interface) {
+        Ok(coalesce) => results.extend(check_coalesce(interface, &coalesce, profile)),
+        Err(e) => log::debug!("{}: ethtool coalesce failed: {:#}", interface, e),
+    }
+    results.extend(check_napi_defer(interface, profile));
+
+    results
+}
+
+/// a number from /proc/sys or /sys
+fn read_u32(path: &str) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// net.core.busy_poll and net.core.busy_read
+fn check_busy_poll(profile: Option<TuningProfile>) -> CheckResult {
+    let (Some(busy_poll), Some(busy_read)) = (
+        read_u32("/proc/sys/net/core/busy_poll"),
+        read_u32("/proc/sys/net/core/busy_read"),
+    ) else {
+        return CheckResult {
+            name: "Busy Polling".to_string(),
+            status: CheckStatus::Info,
+            message: "net.core.busy_poll is not available".to_string(),
+            details: Some("The kernel is built without CONFIG_NET_RX_BUSY_POLL".to_string()),
+        };
+    };
+
+    let message = format!("busy_poll {} µs, busy_read {} µs", busy_poll, busy_read);
+    let Some(profile) = profile else {
+        return CheckResult {
+            name: "Busy Polling".to_string(),
+            status: CheckStatus::Info,
+            message,
+            details: Some(NO_PROFILE.to_string()),
+        };
+    };
+
+    let message = format!("{} ({} profile)", message, profile.as_str());
+    let (status, details) = match profile {
+        TuningProfile::Latency if busy_poll > 0 && busy_read > 0 => (
+            CheckStatus::Pass,
+            "Sockets spin on the NAPI context instead of waiting for an interrupt".to_string(),
+        ),
+        TuningProfile::Latency => (
+            CheckStatus::Warning,
+            format!(
+                "The latency profile expects busy polling\nSet with: sysctl -w net.core.busy_poll={0} net.core.busy_read={0}\nAF_XDP sockets can also opt in one by one with SO_BUSY_POLL and SO_PREFER_BUSY_POLL",
+                BUSY_POLL_USECS
+            ),
+        ),
+        TuningProfile::Throughput if busy_poll == 0 && busy_read == 0 => {
+            (CheckStatus::Pass, "Receive is interrupt driven".to_string())
+        }
+        TuningProfile::Throughput => (
+            CheckStatus::Warning,
+            "Busy polling spins a CPU per polling socket, the throughput profile expects interrupts\nDisable with: sysctl -w net.core.busy_poll=0 net.core.busy_read=0".to_string(),
+        ),
+    };
+
+    CheckResult {
+        name: "Busy Polling".to_string(),
+        status,
+        message,
+        details: Some(details),
+    }
+}
+
+/// interrupt coalescing (ethtool -c) against `profile`
+fn check_coalesce(interface: &str, coalesce: &Coalesce, profile: Option<TuningProfile>) -> Vec<CheckResult> {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let mut settings = Vec::new();
+    for (name, value) in [
+        ("rx-usecs", coalesce.rx_usecs),
+        ("rx-frames", coalesce.rx_max_frames),
+        ("rx-usecs-irq", coalesce.rx_usecs_irq),
+        ("rx-frames-irq", coalesce.rx_max_frames_irq),
+        ("tx-usecs", coalesce.tx_usecs),
+        ("tx-frames", coalesce.tx_max_frames),
+    ] {
+        if let Some(value) = value {
+            settings.push(format!("{} {}", name, value));
+        }
+    }
+    for (name, value) in [
+        ("adaptive-rx", coalesce.adaptive_rx),
+        ("adaptive-tx", coalesce.adaptive_tx),
+        ("cqe-mode-rx", coalesce.cqe_mode_rx),
+        ("cqe-mode-tx", coalesce.cqe_mode_tx),
+    ] {
+        if let Some(value) = value {
+            settings.push(format!("{} {}", name, on_off(value)));
+        }
+    }
+
+    if settings.is_empty() {
+        return Vec::new();
+    }
+
+    let Some(profile) = profile else {
+        return vec![CheckResult {
+            name: format!("{}: Coalescing", interface),
+            status: CheckStatus::Info,
+            message: settings.join(", "),
+            details: Some(NO_PROFILE.to_string()),
+        }];
+    };
+
+    // (problem, ethtool -C argument)
+    let mut deviations: Vec<(String, String)> = Vec::new();
+    match profile {
+        TuningProfile::Latency => {
+            if coalesce.adaptive_rx == Some(true) {
+                deviations.push((
+                    "adaptive-rx is on, it raises rx-usecs under load".to_string(),
+                    "adaptive-rx off".to_string(),
+                ));
+            }
+            if coalesce.adaptive_tx == Some(true) {
+                deviations.push((
+                    "adaptive-tx is on, it delays TX completions".to_string(),
+                    "adaptive-tx off".to_string(),
+                ));
+            }
+            if let Some(usecs) = coalesce.rx_usecs.filter(|u| *u > LATENCY_MAX_RX_USECS) {
+                deviations.push((
+                    format!("rx-usecs {} is above {}", usecs, LATENCY_MAX_RX_USECS),
+                    format!("rx-usecs {}", LATENCY_MAX_RX_USECS),
+                ));
+            }
+        }
+        TuningProfile::Throughput => {
+            let usecs = coalesce
+                .rx_usecs
+                .filter(|u| *u < THROUGHPUT_MIN_RX_USECS && coalesce.adaptive_rx != Some(true));
+            if let Some(usecs) = usecs {
+                deviations.push(match coalesce.adaptive_rx {
+                    Some(_) => (
+                        format!("adaptive-rx is off and rx-usecs {} interrupts for few packets", usecs),
+                        "adaptive-rx on".to_string(),
+                    ),
+                    None => (
+                        format!("rx-usecs {} interrupts for few packets", usecs),
+                        format!("rx-usecs {}", THROUGHPUT_MIN_RX_USECS),
+                    ),
+                });
+            }
+        }
+    }
+
+    let status = if deviations.is_empty() { CheckStatus::Pass } else { CheckStatus::Warning };
+    let details = if deviations.is_empty() {
+        format!("Matches the {} profile", profile.as_str())
+    } else {
+        let (problems, fixes): (Vec<String>, Vec<String>) = deviations.into_iter().unzip();
+        format!(
+            "{}\nFix with: ethtool -C {} {}",
+            problems.join("\n"),
+            interface,
+            fixes.join(" ")
+        )
+    };
+
+    vec![CheckResult {
+        name: format!("{}: Coalescing", interface),
+        status,
+        message: settings.join(", "),
+        details: Some(details),
+    }]
+}
+
+/// napi_defer_hard_irqs and gro_flush_timeout of one netdev
+fn check_napi_defer(interface: &str, profile: Option<TuningProfile>) -> Option<CheckResult> {
+    let defer = read_u32(&format!("/sys/class/net/{}/napi_defer_hard_irqs", interface))?;
+    let timeout = read_u32(&format!("/sys/class/net/{}/gro_flush_timeout", interface))?;
+
+    let message = format!("napi_defer_hard_irqs {}, gro_flush_timeout {} ns", defer, timeout);
+    let fix = format!(
+        "Set with: echo {} > /sys/class/net/{iface}/napi_defer_hard_irqs; echo {} > /sys/class/net/{iface}/gro_flush_timeout",
+        NAPI_DEFER_HARD_IRQS,
+        GRO_FLUSH_TIMEOUT_NS,
+        iface = interface
+    );
+
+    let (status, details) = if defer > 0 && timeout == 0 {
+        // the kernel only defers when the timer can rearm NAPI
+        (
+            CheckStatus::Warning,
+            format!("napi_defer_hard_irqs has no effect without gro_flush_timeout\n{}", fix),
+        )
+    } else {
+        match profile {
+            None => (CheckStatus::Info, NO_PROFILE.to_string()),
+            Some(TuningProfile::Latency) if defer > 0 => (
+                CheckStatus::Pass,
+                "IRQs stay masked while an SO_PREFER_BUSY_POLL socket polls the queue".to_string(),
+            ),
+            Some(TuningProfile::Latency) => (
+                CheckStatus::Warning,
+                format!(
+                    "The latency profile expects deferred IRQs so preferred busy polling is not interrupted\n{}",
+                    fix
+                ),
+            ),
+            Some(TuningProfile::Throughput) => (
+                CheckStatus::Info,
+                "Not part of the throughput profile; deferring IRQs also batches interrupt driven receive".to_string(),
+            ),
+        }
+    };
+
+    Some(CheckResult {
+        name: format!("{}: NAPI Deferral", interface),
+        status,
+        message,
+        details: Some(details),
+    })
+}
+
+/// CPUs in /sys/devices/system/cpu/isolated (isolcpus=), empty when none are
+pub fn isolated_cpus() -> Vec<u32> {
+    fs::read_to_string("/sys/devices/system/cpu/isolated")
+        .map(|list| parse_cpu_list(list.trim()))
+        .unwrap_or_default()
+}
+
+/// kernel cpu list format, "0-3,8,10-11"
+fn parse_cpu_list(list: &str) -> Vec<u32> {
+    list.split(',')
+        .filter(|part| !part.is_empty())
+        .flat_map(|part| match part.split_once('-') {
+            Some((start, end)) => match (start.parse::<u32>(), end.parse::<u32>()) {
+                (Ok(start), Ok(end)) => (start..=end).collect(),
+                _ => Vec::new(),
+            },
+            None => part.parse().into_iter().collect(),
+        })
+        .collect()
+}
+
 fn check_irq_affinity() -> CheckResult {
     // check if irqbalance is running
     let irqbalance_running = std::process::Command::new("pgrep")
@@ -206,8 +489,6 @@ fn check_system_load() -> Result<CheckResult> {
 
     let status = if load_ratio < 0.7 {
         CheckStatus::Pass
-    } else if load_ratio < 0.9 {
-        CheckStatus::Warning
     } else {
         CheckStatus::Warning
     };
@@ -0,0 +1,325 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+use nix::net::if_::if_nametoindex;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, IsTerminal, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::netlink;
+use crate::nic;
+use crate::xsk::{self, XskInfo};
+
+/// normalized counter name -> value, for one interface queue
+type Counters = BTreeMap<String, u64>;
+
+/// one poll of every watched counter
+struct Sample {
+    at: Instant,
+    /// (interface, queue) -> counters, queue None for device-wide counters
+    queues: BTreeMap<(String, Option<u32>), Counters>,
+    sockets: HashMap<u32, XskInfo>,
+}
+
+/// per-second rates of one queue between two samples
+struct QueueRates {
+    interface: String,
+    queue: Option<u32>,
+    /// packets the program returned a verdict for. drivers without an XDP
+    /// packet counter have no XDP_PASS counter either, there it is drop + redirect + tx
+    verdicts: f64,
+    bps: f64,
+    drop: f64,
+    redirect: f64,
+    tx: f64,
+    errors: f64,
+}
+
+struct SocketRates {
+    inode: u32,
+    interface: String,
+    queue: u32,
+    rx_dropped: f64,
+    invalid: f64,
+    rx_full: f64,
+    fill_ring_empty: f64,
+}
+
+/// interfaces worth watching by default: those with XDP attached or an AF_XDP socket
+fn default_interfaces() -> Result<Vec<String>> {
+    let mut interfaces: Vec<String> = netlink::xdp_attachments()?
+        .into_iter()
+        .filter(|a| a.is_attached())
+        .map(|a| a.name)
+        .collect();
+
+    for socket in xsk::xsk_sockets().unwrap_or_default() {
+        let name = socket.interface();
+        if !interfaces.contains(&name) {
+            interfaces.push(name);
+        }
+    }
+
+    if interfaces.is_empty() {
+        bail!("No interface has XDP attached or an AF_XDP socket, name one to watch");
+    }
+    Ok(interfaces)
+}
+
+fn sample(interfaces: &[(String, String)]) -> Sample {
+    let mut queues: BTreeMap<(String, Option<u32>), Counters> = BTreeMap::new();
+
+    for (interface, driver) in interfaces {
+        match nic::xdp_stats(interface, driver) {
+            Ok(stats) => {
+                for stat in stats {
+                    queues
+                        .entry((interface.clone(), stat.queue))
+                        .or_default()
+                        .insert(stat.name, stat.value);
+                }
+            }
+            Err(e) => log::debug!("{}: ethtool stats failed: {:#}", interface, e),
+        }
+    }
+
+    let sockets = xsk::xsk_sockets()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| interfaces.iter().any(|(name, _)| *name == s.interface()))
+        .map(|s| (s.inode, s))
+        .collect();
+
+    Sample {
+        at: Instant::now(),
+        queues,
+        sockets,
+    }
+}
+
+/// increase per second, a counter that went backwards (driver reset) counts from zero
+fn rate(current: u64, previous: u64, secs: f64) -> f64 {
+    let delta = if current >= previous { current - previous } else { current };
+    delta as f64 / secs
+}
+
+fn queue_rates(previous: &Sample, current: &Sample) -> Vec<QueueRates> {
+    let secs = current.at.duration_since(previous.at).as_secs_f64().max(0.001);
+
+    current
+        .queues
+        .iter()
+        .map(|((interface, queue), counters)| {
+            let before = previous.queues.get(&(interface.clone(), *queue));
+            let delta = |name: &str| {
+                let now = counters.get(name).copied().unwrap_or(0);
+                let then = before.and_then(|b| b.get(name)).copied().unwrap_or(now);
+                rate(now, then, secs)
+            };
+            let sum = |filter: &dyn Fn(&str) -> bool| -> f64 {
+                // fold from 0.0, an empty f64 sum() is -0.0 and prints as "-0"
+                counters.keys().filter(|name| filter(name)).map(|name| delta(name)).fold(0.0, |a, b| a + b)
+            };
+
+            let drop = delta("xdp_drop");
+            let redirect = delta("xdp_redirect");
+            let tx = delta("xdp_tx");
+
+            // drivers without an XDP packet counter: the verdicts that are counted, not XDP_PASS
+            let has_packets = counters.keys().any(|n| n.ends_with("packets") && !n.starts_with("xsk"));
+            let verdicts = if has_packets {
+                sum(&|n| n.ends_with("packets") && !n.starts_with("xsk"))
+            } else {
+                drop + redirect + tx
+            };
+
+            QueueRates {
+                interface: interface.clone(),
+                queue: *queue,
+                verdicts,
+                bps: sum(&|n| n.ends_with("bytes") && !n.starts_with("xsk")) * 8.0,
+                drop,
+                redirect,
+                tx,
+                errors: sum(&nic::is_error_stat),
+            }
+        })
+        .collect()
+}
+
+fn socket_rates(previous: &Sample, current: &Sample) -> Vec<SocketRates> {
+    let secs = current.at.duration_since(previous.at).as_secs_f64().max(0.001);
+
+    let mut rates: Vec<SocketRates> = current
+        .sockets
+        .values()
+        .map(|now| {
+            // a socket that just appeared starts from its current counters
+            let then = previous.sockets.get(&now.inode).unwrap_or(now);
+            SocketRates {
+                inode: now.inode,
+                interface: now.interface(),
+                queue: now.queue_id,
+                rx_dropped: rate(now.rx_dropped, then.rx_dropped, secs),
+                invalid: rate(now.rx_invalid + now.tx_invalid, then.rx_invalid + then.tx_invalid, secs),
+                rx_full: rate(now.rx_full, then.rx_full, secs),
+                fill_ring_empty: rate(now.fill_ring_empty, then.fill_ring_empty, secs),
+            }
+        })
+        .collect();
+    rates.sort_by_key(|r| (r.interface.clone(), r.queue, r.inode));
+    rates
+}
+
+/// 1234567.0 -> "1.23M"
+fn human(value: f64) -> String {
+    match value {
+        v if v >= 1e9 => format!("{:.2}G", v / 1e9),
+        v if v >= 1e6 => format!("{:.2}M", v / 1e6),
+        v if v >= 1e3 => format!("{:.2}K", v / 1e3),
+        v => format!("{:.0}", v),
+    }
+}
+
+fn print_table(queues: &[QueueRates], sockets: &[SocketRates], interval: Duration) {
+    println!(
+        "{}",
+        format!("XDP counters, every {} ms (Ctrl-C to stop)", interval.as_millis()).cyan().bold()
+    );
+    println!();
+    println!(
+        "{:<16} {:>5} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "IFACE", "QUEUE", "VERDICT/s", "BPS", "DROP/s", "REDIR/s", "TX/s", "ERR/s"
+    );
+
+    for q in queues {
+        let line = format!(
+            "{:<16} {:>5} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            q.interface,
+            q.queue.map_or("all".to_string(), |q| q.to_string()),
+            human(q.verdicts),
+            human(q.bps),
+            human(q.drop),
+            human(q.redirect),
+            human(q.tx),
+            human(q.errors)
+        );
+        // errors mean lost packets, XDP_DROP is the program's own decision
+        if q.errors > 0.0 {
+            println!("{}", line.red().bold());
+        } else if q.drop > 0.0 {
+            println!("{}", line.yellow());
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    if sockets.is_empty() {
+        return;
+    }
+
+    println!();
+    println!(
+        "{:<10} {:<16} {:>5} {:>11} {:>11} {:>11} {:>13}",
+        "XSK", "IFACE", "QUEUE", "RX_DROP/s", "INVALID/s", "RX_FULL/s", "FILL_EMPTY/s"
+    );
+    for s in sockets {
+        let line = format!(
+            "{:<10} {:<16} {:>5} {:>11} {:>11} {:>11} {:>13}",
+            s.inode,
+            s.interface,
+            s.queue,
+            human(s.rx_dropped),
+            human(s.invalid),
+            human(s.rx_full),
+            human(s.fill_ring_empty)
+        );
+        if s.rx_dropped > 0.0 || s.invalid > 0.0 {
+            println!("{}", line.red().bold());
+        } else if s.rx_full > 0.0 || s.fill_ring_empty > 0.0 {
+            println!("{}", line.yellow());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+fn print_json(queues: &[QueueRates], sockets: &[SocketRates]) -> Result<()> {
+    let value = serde_json::json!({
+        "queues": queues.iter().map(|q| serde_json::json!({
+            "interface": q.interface,
+            "queue": q.queue,
+            "verdicts_per_sec": q.verdicts,
+            "bps": q.bps,
+            "drop_per_sec": q.drop,
+            "redirect_per_sec": q.redirect,
+            "tx_per_sec": q.tx,
+            "errors_per_sec": q.errors,
+        })).collect::<Vec<_>>(),
+        "xsk": sockets.iter().map(|s| serde_json::json!({
+            "inode": s.inode,
+            "interface": s.interface,
+            "queue": s.queue,
+            "rx_dropped_per_sec": s.rx_dropped,
+            "invalid_per_sec": s.invalid,
+            "rx_full_per_sec": s.rx_full,
+            "fill_ring_empty_per_sec": s.fill_ring_empty,
+        })).collect::<Vec<_>>(),
+    });
+    // one line per sample so the output can be piped into jq
+    println!("{}", serde_json::to_string(&value)?);
+    Ok(())
+}
+
+/// poll the XDP queue counters and AF_XDP socket stats and print rates until
+/// interrupted, or `count` times
+pub fn watch(interface: Option<&str>, interval: Duration, count: Option<u64>, json: bool) -> Result<()> {
+    let interfaces = match interface {
+        Some(interface) => {
+            // a typo would otherwise print an empty table forever
+            if if_nametoindex(interface).is_err() {
+                bail!("No such interface: {}", interface);
+            }
+            vec![interface.to_string()]
+        }
+        None => default_interfaces()?,
+    };
+    let interfaces: Vec<(String, String)> = interfaces
+        .into_iter()
+        .map(|name| {
+            let driver = nic::interface_driver(&name).unwrap_or_default();
+            (name, driver)
+        })
+        .collect();
+
+    // clearing the screen only makes sense on a terminal
+    let redraw = !json && io::stdout().is_terminal();
+
+    let mut previous = sample(&interfaces);
+    let mut printed = 0;
+
+    while count.is_none_or(|count| printed < count) {
+        thread::sleep(interval);
+        let current = sample(&interfaces);
+
+        let queues = queue_rates(&previous, &current);
+        let sockets = socket_rates(&previous, &current);
+
+        if json {
+            print_json(&queues, &sockets)?;
+        } else {
+            if redraw {
+                print!("\x1b[2J\x1b[H");
+            } else if printed > 0 {
+                println!();
+            }
+            print_table(&queues, &sockets, interval);
+        }
+        io::stdout().flush()?;
+
+        previous = current;
+        printed += 1;
+    }
+
+    Ok(())
+}
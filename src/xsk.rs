@@ -0,0 +1,466 @@
+use anyhow::Result;
+use libc::{
+    c_void, sockaddr_xdp, socklen_t, xdp_mmap_offsets, xdp_options, xdp_umem_reg, AF_XDP,
+    SOCK_CLOEXEC, SOCK_RAW, SOL_XDP, XDP_COPY, XDP_MMAP_OFFSETS, XDP_OPTIONS,
+    XDP_OPTIONS_ZEROCOPY, XDP_RX_RING, XDP_UMEM_COMPLETION_RING, XDP_UMEM_FILL_RING,
+    XDP_UMEM_REG, XDP_USE_NEED_WAKEUP, XDP_ZEROCOPY,
+};
+use netlink_sys::protocols::NETLINK_SOCK_DIAG;
+use nix::errno::Errno;
+use nix::net::if_::{if_indextoname, if_nametoindex};
+use procfs::process::FDTarget;
+use std::collections::HashMap;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::{mem, ptr};
+
+use crate::netlink::{self, ne_u32, ne_u64, NLM_F_DUMP};
+use crate::output::{CheckResult, CheckStatus};
+
+/// small UMEM, big enough to populate the rings
+const FRAME_SIZE: u32 = 4096;
+const NUM_FRAMES: u32 = 64;
+const RING_SIZE: u32 = 64;
+
+/// page-aligned anonymous mapping registered as UMEM
+struct Umem {
+    addr: *mut c_void,
+    len: usize,
+}
+
+impl Umem {
+    fn new(len: usize) -> Result<Self, Errno> {
+        let addr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(Errno::last());
+        }
+        Ok(Self { addr, len })
+    }
+}
+
+impl Drop for Umem {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.addr, self.len);
+        }
+    }
+}
+
+/// AF_XDP socket with UMEM and fill/completion/RX rings configured
+struct XskSocket {
+    fd: OwnedFd,
+    // must outlive the socket registration
+    _umem: Umem,
+}
+
+/// setup stage that failed together with the errno the kernel returned
+struct SetupError {
+    stage: &'static str,
+    errno: Errno,
+}
+
+impl XskSocket {
+    fn new() -> Result<Self, SetupError> {
+        let fd = unsafe { libc::socket(AF_XDP, SOCK_RAW | SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(SetupError { stage: "socket(AF_XDP)", errno: Errno::last() });
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let umem = Umem::new((FRAME_SIZE * NUM_FRAMES) as usize)
+            .map_err(|errno| SetupError { stage: "UMEM mmap", errno })?;
+
+        let mut reg: xdp_umem_reg = unsafe { mem::zeroed() };
+        reg.addr = umem.addr as u64;
+        reg.len = umem.len as u64;
+        reg.chunk_size = FRAME_SIZE;
+        reg.headroom = 0;
+
+        setsockopt(&fd, XDP_UMEM_REG, &reg)
+            .map_err(|errno| SetupError { stage: "XDP_UMEM_REG", errno })?;
+
+        for (stage, opt) in [
+            ("XDP_UMEM_FILL_RING", XDP_UMEM_FILL_RING),
+            ("XDP_UMEM_COMPLETION_RING", XDP_UMEM_COMPLETION_RING),
+            ("XDP_RX_RING", XDP_RX_RING),
+        ] {
+            setsockopt(&fd, opt, &RING_SIZE).map_err(|errno| SetupError { stage, errno })?;
+        }
+
+        Ok(Self { fd, _umem: umem })
+    }
+
+    fn bind(&self, ifindex: u32, queue: u32, flags: u16) -> Result<(), Errno> {
+        let mut addr: sockaddr_xdp = unsafe { mem::zeroed() };
+        addr.sxdp_family = AF_XDP as u16;
+        addr.sxdp_flags = flags;
+        addr.sxdp_ifindex = ifindex;
+        addr.sxdp_queue_id = queue;
+
+        let res = unsafe {
+            libc::bind(
+                self.fd.as_raw_fd(),
+                &addr as *const _ as *const libc::sockaddr,
+                mem::size_of::<sockaddr_xdp>() as socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(Errno::last());
+        }
+        Ok(())
+    }
+
+    /// XDP_OPTIONS (5.3+), None when the kernel cannot tell
+    fn zerocopy_enabled(&self) -> Option<bool> {
+        let mut opts: xdp_options = unsafe { mem::zeroed() };
+        getsockopt(&self.fd, XDP_OPTIONS, &mut opts)
+            .ok()
+            .map(|_| opts.flags & XDP_OPTIONS_ZEROCOPY != 0)
+    }
+
+    /// ring flags (and so XDP_RING_NEED_WAKEUP) are only mapped with v2 offsets
+    fn ring_flags_exposed(&self) -> bool {
+        let mut offsets: xdp_mmap_offsets = unsafe { mem::zeroed() };
+        matches!(
+            getsockopt(&self.fd, XDP_MMAP_OFFSETS, &mut offsets),
+            Ok(len) if len == mem::size_of::<xdp_mmap_offsets>()
+        )
+    }
+}
+
+fn setsockopt<T>(fd: &OwnedFd, opt: i32, value: &T) -> Result<(), Errno> {
+    let res = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            SOL_XDP,
+            opt,
+            value as *const T as *const c_void,
+            mem::size_of::<T>() as socklen_t,
+        )
+    };
+    if res < 0 {
+        return Err(Errno::last());
+    }
+    Ok(())
+}
+
+/// returns the option length written by the kernel
+fn getsockopt<T>(fd: &OwnedFd, opt: i32, value: &mut T) -> Result<usize, Errno> {
+    let mut len = mem::size_of::<T>() as socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            SOL_XDP,
+            opt,
+            value as *mut T as *mut c_void,
+            &mut len,
+        )
+    };
+    if res < 0 {
+        return Err(Errno::last());
+    }
+    Ok(len as usize)
+}
+
+fn errno_text(errno: Errno) -> String {
+    format!("{} (errno {})", errno, errno as i32)
+}
+
+/// open an XSK and bind it to `queue`, zero-copy first and copy as fallback.
+/// zero-copy failing is only a warning when `expect_zerocopy` is set.
+pub fn probe_bind(interface: &str, queue: u32, expect_zerocopy: bool) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let ifindex = match if_nametoindex(interface) {
+        Ok(ifindex) => ifindex,
+        Err(e) => {
+            results.push(CheckResult {
+                name: "AF_XDP Socket".to_string(),
+                status: CheckStatus::Error,
+                message: format!("Interface {} not found", interface),
+                details: Some(errno_text(e)),
+            });
+            return results;
+        }
+    };
+
+    match XskSocket::new() {
+        Ok(_) => results.push(CheckResult {
+            name: "AF_XDP Socket".to_string(),
+            status: CheckStatus::Pass,
+            message: "Created XSK with UMEM and fill/completion/RX rings".to_string(),
+            details: Some(format!(
+                "UMEM: {} x {} byte frames\nRing size: {} entries",
+                NUM_FRAMES, FRAME_SIZE, RING_SIZE
+            )),
+        }),
+        Err(e) => {
+            results.push(CheckResult {
+                name: "AF_XDP Socket".to_string(),
+                status: CheckStatus::Fail,
+                message: format!("{} failed: {}", e.stage, e.errno),
+                details: Some(format!(
+                    "{}\nEAFNOSUPPORT means the kernel was built without CONFIG_XDP_SOCKETS",
+                    errno_text(e.errno)
+                )),
+            });
+            return results;
+        }
+    }
+
+    // (socket, mode, need_wakeup accepted)
+    let mut accepted = None;
+
+    for (check_name, mode, flags) in [
+        ("Zero-Copy Bind", "zero-copy", XDP_ZEROCOPY),
+        ("Copy Bind", "copy", XDP_COPY),
+    ] {
+        let attempt = try_bind(ifindex, queue, flags);
+
+        match attempt {
+            Ok((socket, need_wakeup)) => {
+                results.push(CheckResult {
+                    name: check_name.to_string(),
+                    status: CheckStatus::Pass,
+                    message: format!("{} queue {} accepted {} bind", interface, queue, mode),
+                    details: None,
+                });
+                accepted = Some((socket, mode, need_wakeup));
+                break;
+            }
+            Err(errno) => {
+                let status = if flags == XDP_COPY {
+                    CheckStatus::Fail
+                } else if expect_zerocopy {
+                    CheckStatus::Warning
+                } else {
+                    CheckStatus::Info
+                };
+
+                results.push(CheckResult {
+                    name: check_name.to_string(),
+                    status,
+                    message: format!("{} bind rejected: {}", mode, errno),
+                    details: Some(errno_text(errno)),
+                });
+            }
+        }
+    }
+
+    let Some((socket, mode, need_wakeup)) = accepted else {
+        return results;
+    };
+
+    let confirmed = match socket.zerocopy_enabled() {
+        Some(true) => "XDP_OPTIONS reports zero-copy",
+        Some(false) => "XDP_OPTIONS reports copy mode",
+        None => "XDP_OPTIONS not supported by this kernel",
+    };
+
+    results.push(CheckResult {
+        name: "XSK Bind Mode".to_string(),
+        status: if mode == "zero-copy" || !expect_zerocopy {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Warning
+        },
+        message: format!("Kernel accepted {} mode on {} queue {}", mode, interface, queue),
+        details: Some(confirmed.to_string()),
+    });
+
+    let flags_exposed = socket.ring_flags_exposed();
+    results.push(CheckResult {
+        name: "XDP_USE_NEED_WAKEUP".to_string(),
+        status: if need_wakeup && flags_exposed { CheckStatus::Pass } else { CheckStatus::Warning },
+        message: if need_wakeup && flags_exposed {
+            "need_wakeup is honoured".to_string()
+        } else {
+            "need_wakeup is not supported".to_string()
+        },
+        details: Some(if need_wakeup {
+            format!(
+                "Bind accepted the flag, ring flags {} in XDP_MMAP_OFFSETS",
+                if flags_exposed { "present" } else { "missing" }
+            )
+        } else {
+            "Bind only succeeded without XDP_USE_NEED_WAKEUP (kernel < 5.4?). Busy polling will cost extra syscalls.".to_string()
+        }),
+    });
+
+    results
+}
+
+/// bind with need_wakeup, retrying without it when the kernel rejects the flag
+fn try_bind(ifindex: u32, queue: u32, flags: u16) -> Result<(XskSocket, bool), Errno> {
+    let socket = XskSocket::new().map_err(|e| e.errno)?;
+    match socket.bind(ifindex, queue, flags | XDP_USE_NEED_WAKEUP) {
+        Ok(()) => return Ok((socket, true)),
+        Err(Errno::EINVAL) => {}
+        Err(errno) => return Err(errno),
+    }
+
+    // a failed bind may leave the UMEM half assigned, start over
+    let socket = XskSocket::new().map_err(|e| e.errno)?;
+    socket.bind(ifindex, queue, flags)?;
+    Ok((socket, false))
+}
+
+// linux/sock_diag.h, linux/xdp_diag.h
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+
+const XDP_SHOW_INFO: u32 = 1 << 0;
+const XDP_SHOW_RING_CFG: u32 = 1 << 1;
+const XDP_SHOW_UMEM: u32 = 1 << 2;
+const XDP_SHOW_MEMINFO: u32 = 1 << 3;
+const XDP_SHOW_STATS: u32 = 1 << 4;
+
+const XDP_DIAG_INFO: u16 = 1;
+const XDP_DIAG_UID: u16 = 2;
+const XDP_DIAG_RX_RING: u16 = 3;
+const XDP_DIAG_TX_RING: u16 = 4;
+const XDP_DIAG_UMEM: u16 = 5;
+const XDP_DIAG_UMEM_FILL_RING: u16 = 6;
+const XDP_DIAG_UMEM_COMPLETION_RING: u16 = 7;
+const XDP_DIAG_STATS: u16 = 9;
+
+const XDP_DU_F_ZEROCOPY: u32 = 1 << 0;
+
+/// sizeof(struct xdp_diag_msg)
+const XDP_DIAG_MSG_LEN: usize = 16;
+
+/// one AF_XDP socket as reported by xdp_diag
+#[derive(Debug, Clone, Default)]
+pub struct XskInfo {
+    pub inode: u32,
+    pub uid: Option<u32>,
+    pub ifindex: u32,
+    pub queue_id: u32,
+    pub umem_id: u32,
+    pub umem_size: u64,
+    pub chunk_size: u32,
+    pub headroom: u32,
+    pub zerocopy: bool,
+    pub rx_ring: Option<u32>,
+    pub tx_ring: Option<u32>,
+    pub fill_ring: Option<u32>,
+    pub completion_ring: Option<u32>,
+    pub rx_dropped: u64,
+    pub rx_invalid: u64,
+    pub rx_full: u64,
+    pub fill_ring_empty: u64,
+    pub tx_invalid: u64,
+    pub tx_ring_empty: u64,
+}
+
+impl XskInfo {
+    pub fn interface(&self) -> String {
+        interface_name(self.ifindex)
+    }
+}
+
+pub fn interface_name(ifindex: u32) -> String {
+    if_indextoname(ifindex)
+        .ok()
+        .and_then(|name| name.into_string().ok())
+        .unwrap_or_else(|| format!("ifindex {}", ifindex))
+}
+
+/// dump every AF_XDP socket over NETLINK_SOCK_DIAG (needs the xsk_diag module)
+pub fn xsk_sockets() -> Result<Vec<XskInfo>> {
+    // struct xdp_diag_req
+    let mut req = Vec::with_capacity(20);
+    req.push(AF_XDP as u8); // sdiag_family
+    req.push(0); // sdiag_protocol
+    req.extend_from_slice(&0u16.to_ne_bytes()); // pad
+    req.extend_from_slice(&0u32.to_ne_bytes()); // xdiag_ino, 0 = all
+    req.extend_from_slice(
+        &(XDP_SHOW_INFO | XDP_SHOW_RING_CFG | XDP_SHOW_UMEM | XDP_SHOW_MEMINFO | XDP_SHOW_STATS)
+            .to_ne_bytes(),
+    );
+    req.extend_from_slice(&[0xff; 8]); // xdiag_cookie, INET_DIAG_NOCOOKIE
+
+    let replies = netlink::request(NETLINK_SOCK_DIAG, SOCK_DIAG_BY_FAMILY, NLM_F_DUMP, &req)?;
+
+    let mut sockets = Vec::new();
+    for reply in replies {
+        if reply.len() < XDP_DIAG_MSG_LEN {
+            continue;
+        }
+
+        let mut info = XskInfo {
+            inode: ne_u32(&reply, 4),
+            ..Default::default()
+        };
+
+        for (kind, value) in netlink::attributes(&reply[XDP_DIAG_MSG_LEN..]) {
+            match kind {
+                XDP_DIAG_INFO => {
+                    info.ifindex = ne_u32(value, 0);
+                    info.queue_id = ne_u32(value, 4);
+                }
+                XDP_DIAG_UID => info.uid = Some(ne_u32(value, 0)),
+                XDP_DIAG_RX_RING => info.rx_ring = Some(ne_u32(value, 0)),
+                XDP_DIAG_TX_RING => info.tx_ring = Some(ne_u32(value, 0)),
+                XDP_DIAG_UMEM_FILL_RING => info.fill_ring = Some(ne_u32(value, 0)),
+                XDP_DIAG_UMEM_COMPLETION_RING => info.completion_ring = Some(ne_u32(value, 0)),
+                XDP_DIAG_UMEM => {
+                    // struct xdp_diag_umem
+                    info.umem_size = ne_u64(value, 0);
+                    info.umem_id = ne_u32(value, 8);
+                    info.chunk_size = ne_u32(value, 16);
+                    info.headroom = ne_u32(value, 20);
+                    info.zerocopy = ne_u32(value, 32) & XDP_DU_F_ZEROCOPY != 0;
+                }
+                XDP_DIAG_STATS => {
+                    // struct xdp_diag_stats
+                    info.rx_dropped = ne_u64(value, 0);
+                    info.rx_invalid = ne_u64(value, 8);
+                    info.rx_full = ne_u64(value, 16);
+                    info.fill_ring_empty = ne_u64(value, 24);
+                    info.tx_invalid = ne_u64(value, 32);
+                    info.tx_ring_empty = ne_u64(value, 40);
+                }
+                _ => {}
+            }
+        }
+
+        sockets.push(info);
+    }
+
+    Ok(sockets)
+}
+
+/// socket inode -> (pid, comm) of every process holding it
+pub fn socket_owners() -> HashMap<u64, Vec<(i32, String)>> {
+    let mut owners: HashMap<u64, Vec<(i32, String)>> = HashMap::new();
+
+    let Ok(processes) = procfs::process::all_processes() else {
+        return owners;
+    };
+
+    for process in processes.flatten() {
+        let Ok(fds) = process.fd() else {
+            continue;
+        };
+        let comm = process.stat().map(|s| s.comm).unwrap_or_else(|_| "?".to_string());
+
+        for fd in fds.flatten() {
+            if let FDTarget::Socket(inode) = fd.target {
+                let entry = owners.entry(inode).or_default();
+                if !entry.iter().any(|(pid, _)| *pid == process.pid()) {
+                    entry.push((process.pid(), comm.clone()));
+                }
+            }
+        }
+    }
+
+    owners
+}